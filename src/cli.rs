@@ -1,24 +1,105 @@
-use log::info;
-use rbxlx_to_rojo::{filesystem::FileSystem, process_instructions};
+use log::{debug, info};
+use rbxlx_to_rojo::{
+    dry_run::DryRun,
+    error::ConversionError,
+    filesystem::{FileSystem, OutputMode},
+    model_top_instance, process_instructions_with_options,
+    rebuild::{rebuild, write_place},
+    structures::{Options, ProjectKind},
+    verify::verify,
+};
 use std::{
     borrow::Cow,
+    ffi::OsStr,
     fmt,
     fs,
     io::{self, BufReader, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
+    process,
     sync::{Arc, RwLock},
 };
 
+const USAGE: &str = "\
+Converts a Roblox place or model file into a Rojo project.
+
+Usage: rbxlx-to-rojo [OPTIONS] [INPUT] [OUTPUT]
+       rbxlx-to-rojo --rebuild [PROJECT] [PLACE]
+
+Options:
+  -i, --input <PATH>           The .rbxl, .rbxlx, .rbxm or .rbxmx file to convert
+  -o, --output <PATH>          The folder to create the project in [default: the input's folder]
+      --project-name <NAME>    The name of the project [default: the model's top instance, or the input's file name]
+                               (the project's folder is always named after the input file)
+      --config <PATH>          A JSON file of conversion options, such as respected_services
+      --include <PATTERN>      Only convert instances matching the pattern, such as ServerScriptService.**
+                               (can be repeated)
+      --exclude <PATTERN>      Leave out instances matching the pattern, such as Workspace.**.Plugins_*
+                               (can be repeated)
+      --export-properties      Write non-default properties into meta files
+      --no-service-properties  Leave services' non-default properties out of default.project.json
+  -f, --force                  Replace the project, or with --rebuild the place, if it already exists
+      --merge                  Update an existing project, only rewriting files that changed
+      --prune                  With --merge, delete files that are no longer part of the project
+      --as-place               Convert a .rbxm or .rbxmx like a place, treating its top instances as services
+      --dry-run                Print the files and project that would be created, without writing anything
+      --verify                 After converting, build the project back and check that every script survived
+      --rebuild                Build a place or model back from a converted project's folder,
+                               into an .rbxl, .rbxlx, .rbxm or .rbxmx
+                               [default: the folder's name with .rbxlx]
+      --interactive            Choose any missing paths with a file dialog
+      --no-dialog              Never open a file dialog
+  -v, --verbose                Log every step of the conversion
+  -h, --help                   Print this message
+
+Exit codes:
+  0  The project (or with --rebuild, the place) was created
+  1  The place couldn't be converted
+  2  The arguments were invalid
+  3  The input file couldn't be decoded
+  4  A file couldn't be read or written
+  5  No file was chosen in the file dialog
+  6  The project already exists and neither --force nor --merge was passed,
+     or with --rebuild, the place already exists and --force wasn't passed
+  7  --verify found scripts that were missing, extra or altered in the project
+";
+
 #[derive(Debug)]
 enum Problem {
     BinaryDecodeError(rbx_binary::DecodeError),
+    ConversionError(ConversionError),
+    InvalidConfig(PathBuf, serde_json::Error),
     InvalidFile,
     IoError(&'static str, io::Error),
+    #[cfg(not(feature = "dialog"))]
+    DialogUnavailable,
+    #[cfg(feature = "dialog")]
     NFDCancel,
+    #[cfg(feature = "dialog")]
     NFDError(String),
+    PlaceExists(PathBuf),
+    Usage(String),
+    VerificationFailed(usize),
     XMLDecodeError(rbx_xml::DecodeError),
 }
 
+impl Problem {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Problem::ConversionError(ConversionError::OutputNotEmpty(_))
+            | Problem::PlaceExists(_) => 6,
+            Problem::ConversionError(_) => 1,
+            Problem::InvalidConfig(_, _) | Problem::Usage(_) => 2,
+            Problem::BinaryDecodeError(_) | Problem::InvalidFile | Problem::XMLDecodeError(_) => 3,
+            Problem::IoError(_, _) => 4,
+            #[cfg(not(feature = "dialog"))]
+            Problem::DialogUnavailable => 5,
+            #[cfg(feature = "dialog")]
+            Problem::NFDCancel | Problem::NFDError(_) => 5,
+            Problem::VerificationFailed(_) => 7,
+        }
+    }
+}
+
 impl fmt::Display for Problem {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -28,6 +109,22 @@ impl fmt::Display for Problem {
                 error,
             ),
 
+            Problem::ConversionError(ConversionError::OutputNotEmpty(path)) => write!(
+                formatter,
+                "{} already exists and isn't empty, pass --force to replace it or --merge to update it",
+                path.display(),
+            ),
+
+            Problem::ConversionError(error) => write!(
+                formatter,
+                "While converting the place file, {}",
+                error,
+            ),
+
+            Problem::InvalidConfig(path, error) => {
+                write!(formatter, "The config file {} isn't valid: {}", path.display(), error)
+            }
+
             Problem::InvalidFile => {
                 write!(formatter, "The file provided does not have a recognized file extension")
             }
@@ -36,14 +133,38 @@ impl fmt::Display for Problem {
                 write!(formatter, "While attempting to {}, {}", doing_what, error)
             }
 
+            #[cfg(not(feature = "dialog"))]
+            Problem::DialogUnavailable => write!(
+                formatter,
+                "This build of rbxlx-to-rojo has no file dialogs, pass the paths as arguments instead",
+            ),
+
+            #[cfg(feature = "dialog")]
             Problem::NFDCancel => write!(formatter, "Didn't choose a file."),
 
+            #[cfg(feature = "dialog")]
             Problem::NFDError(error) => write!(
                 formatter,
                 "Something went wrong when choosing a file: {}",
                 error,
             ),
 
+            Problem::PlaceExists(path) => write!(
+                formatter,
+                "{} already exists, pass --force to replace it",
+                path.display(),
+            ),
+
+            Problem::Usage(message) => {
+                write!(formatter, "{}\n\nRun with --help to see every option.", message)
+            }
+
+            Problem::VerificationFailed(count) => write!(
+                formatter,
+                "{} scripts didn't make it into the project intact, see the log for which",
+                count,
+            ),
+
             Problem::XMLDecodeError(error) => write!(
                 formatter,
                 "While attempting to decode the place file, at {} rbx_xml didn't know what to do",
@@ -78,9 +199,271 @@ impl log::Log for WrappedLogger {
     fn flush(&self) {}
 }
 
+#[derive(Debug, Default)]
+struct Arguments {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    project_name: Option<String>,
+    config: Option<PathBuf>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    as_place: bool,
+    dry_run: bool,
+    export_properties: bool,
+    force: bool,
+    help: bool,
+    interactive: bool,
+    merge: bool,
+    no_dialog: bool,
+    no_service_properties: bool,
+    prune: bool,
+    rebuild: bool,
+    verbose: bool,
+    verify: bool,
+}
+
+impl Arguments {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Problem> {
+        let mut arguments = Arguments::default();
+        let mut positional = Vec::new();
+
+        while let Some(arg) = args.next() {
+            // Both --flag value and --flag=value are accepted
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_owned(), Some(value.to_owned()))
+                }
+                _ => (arg, None),
+            };
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Problem::Usage(format!("{} needs a value", flag)))
+            };
+
+            // Switches are turned on by being there, so --force=false is a mistake
+            let switch = || match inline_value {
+                Some(_) => Err(Problem::Usage(format!("{} doesn't take a value", flag))),
+                None => Ok(true),
+            };
+
+            match flag.as_str() {
+                "-i" | "--input" => arguments.input = Some(value()?.into()),
+                "-o" | "--output" => arguments.output = Some(value()?.into()),
+                "--project-name" => arguments.project_name = Some(value()?),
+                "--config" => arguments.config = Some(value()?.into()),
+                "--include" => arguments.include.push(value()?),
+                "--exclude" => arguments.exclude.push(value()?),
+                "--as-place" => arguments.as_place = switch()?,
+                "--dry-run" => arguments.dry_run = switch()?,
+                "--export-properties" => arguments.export_properties = switch()?,
+                "-f" | "--force" => arguments.force = switch()?,
+                "-h" | "--help" => arguments.help = switch()?,
+                "--interactive" => arguments.interactive = switch()?,
+                "--merge" => arguments.merge = switch()?,
+                "--prune" => arguments.prune = switch()?,
+                "--rebuild" => arguments.rebuild = switch()?,
+                "--no-dialog" => arguments.no_dialog = switch()?,
+                "--no-service-properties" => arguments.no_service_properties = switch()?,
+                "-v" | "--verbose" => arguments.verbose = switch()?,
+                "--verify" => arguments.verify = switch()?,
+                other if other.starts_with('-') => {
+                    return Err(Problem::Usage(format!("Unknown option {}", other)));
+                }
+                _ => positional.push(flag),
+            }
+        }
+
+        if arguments.interactive && arguments.no_dialog {
+            return Err(Problem::Usage(
+                "--interactive and --no-dialog can't be used together".to_owned(),
+            ));
+        }
+
+        if arguments.force && arguments.merge {
+            return Err(Problem::Usage("--force and --merge can't be used together".to_owned()));
+        }
+
+        if arguments.prune && !arguments.merge {
+            return Err(Problem::Usage("--prune only works with --merge".to_owned()));
+        }
+
+        if arguments.rebuild && (arguments.dry_run || arguments.merge) {
+            return Err(Problem::Usage(
+                "--rebuild can't be used with --dry-run or --merge".to_owned(),
+            ));
+        }
+
+        if arguments.verify && (arguments.dry_run || arguments.rebuild) {
+            return Err(Problem::Usage(
+                "--verify can't be used with --dry-run or --rebuild".to_owned(),
+            ));
+        }
+
+        // rbxlx-to-rojo INPUT OUTPUT is still supported
+        let mut positional = positional.into_iter();
+        if arguments.input.is_none() {
+            arguments.input = positional.next().map(PathBuf::from);
+        }
+
+        if arguments.output.is_none() {
+            arguments.output = positional.next().map(PathBuf::from);
+        }
+
+        if let Some(extra) = positional.next() {
+            return Err(Problem::Usage(format!("Unexpected argument {}", extra)));
+        }
+
+        Ok(arguments)
+    }
+
+    fn dialogs_allowed(&self) -> bool {
+        self.interactive && !self.no_dialog
+    }
+
+    // Options come from --config, and anything it leaves out keeps its default.
+    // --include and --exclude add to the patterns it has, and the property flags override it
+    fn options(&self) -> Result<Options, Problem> {
+        let mut options = match &self.config {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .map_err(|error| Problem::IoError("read the config file", error))?;
+                serde_json::from_str(&contents)
+                    .map_err(|error| Problem::InvalidConfig(path.clone(), error))?
+            }
+            None => Options::default(),
+        };
+
+        options.include.extend(self.include.iter().cloned());
+        options.exclude.extend(self.exclude.iter().cloned());
+
+        if self.export_properties {
+            options.export_properties = true;
+        }
+
+        if self.no_service_properties {
+            options.service_properties = false;
+        }
+
+        Ok(options)
+    }
+
+    fn output_mode(&self) -> OutputMode {
+        if self.force {
+            OutputMode::Force
+        } else if self.merge {
+            OutputMode::Merge { prune: self.prune }
+        } else {
+            OutputMode::Fresh
+        }
+    }
+}
+
+#[cfg(feature = "dialog")]
+fn choose_place_file() -> Result<PathBuf, Problem> {
+    match nfd::open_file_dialog(Some("rbxl,rbxm,rbxlx,rbxmx"), None)
+        .map_err(|error| Problem::NFDError(error.to_string()))?
+    {
+        nfd::Response::Okay(path) => Ok(PathBuf::from(path)),
+        nfd::Response::Cancel => Err(Problem::NFDCancel),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(feature = "dialog")]
+fn choose_output_folder(start: &Path) -> Result<PathBuf, Problem> {
+    match nfd::open_pick_folder(Some(&start.to_string_lossy()))
+        .map_err(|error| Problem::NFDError(error.to_string()))?
+    {
+        nfd::Response::Okay(path) => Ok(PathBuf::from(path)),
+        nfd::Response::Cancel => Err(Problem::NFDCancel),
+        _ => unreachable!(),
+    }
+}
+
+// Without the dialog feature the binary has no GUI dependencies at all
+#[cfg(not(feature = "dialog"))]
+fn choose_place_file() -> Result<PathBuf, Problem> {
+    Err(Problem::DialogUnavailable)
+}
+
+#[cfg(not(feature = "dialog"))]
+fn choose_output_folder(_start: &Path) -> Result<PathBuf, Problem> {
+    Err(Problem::DialogUnavailable)
+}
+
+// The project folder is created inside the output folder and replaced by --force, so its name
+// has to be one plain path component. Anything else, like .. or A/B, would point somewhere else.
+fn project_folder_name(file_path: &Path) -> Result<&OsStr, Problem> {
+    let stem = file_path.file_stem().ok_or(Problem::InvalidFile)?;
+    let mut components = Path::new(stem).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) => Ok(name),
+        _ => Err(Problem::Usage(format!(
+            "{} can't be used as the project's folder name",
+            stem.to_string_lossy(),
+        ))),
+    }
+}
+
+// Goes the other way, turning a project back into a place file
+fn rebuild_place(arguments: &Arguments) -> Result<(), Problem> {
+    let project_folder = arguments.input.clone().ok_or_else(|| {
+        Problem::Usage("No project folder given, pass one with --input".to_owned())
+    })?;
+
+    let place_path = arguments
+        .output
+        .clone()
+        .unwrap_or_else(|| project_folder.with_extension("rbxlx"));
+
+    let binary = match place_path
+        .extension()
+        .map(|extension| extension.to_string_lossy())
+    {
+        Some(Cow::Borrowed("rbxlx")) | Some(Cow::Borrowed("rbxmx")) => false,
+        Some(Cow::Borrowed("rbxl")) | Some(Cow::Borrowed("rbxm")) => true,
+        _ => return Err(Problem::InvalidFile),
+    };
+
+    info!("Rebuilding the place from {}", project_folder.display());
+    let tree = rebuild(&project_folder).map_err(Problem::ConversionError)?;
+
+    // Without --output the place goes next to the project, which is often where the
+    // original place file is, so nothing is replaced unless --force says so
+    let place_file = if arguments.force {
+        fs::File::create(&place_path)
+    } else {
+        fs::File::create_new(&place_path)
+    }
+    .map_err(|error| match error.kind() {
+        io::ErrorKind::AlreadyExists => Problem::PlaceExists(place_path.clone()),
+        _ => Problem::IoError("create the place file", error),
+    })?;
+
+    let place_file = io::BufWriter::new(place_file);
+    write_place(&tree, place_file, binary).map_err(Problem::ConversionError)?;
+
+    info!("Done! Wrote {}", place_path.display());
+    Ok(())
+}
+
 fn routine() -> Result<(), Problem> {
+    let arguments = Arguments::parse(std::env::args().skip(1))?;
+    if arguments.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
     let env_logger = env_logger::Builder::new()
-        .filter_level(log::LevelFilter::Info)
+        .filter_level(if arguments.verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Info
+        })
         .build();
 
     let log_file = Arc::new(RwLock::new(None));
@@ -89,22 +472,30 @@ fn routine() -> Result<(), Problem> {
         log_file: Arc::clone(&log_file),
     };
 
+    log::set_max_level(logger.log.filter());
     log::set_boxed_logger(Box::new(logger)).unwrap();
-    log::set_max_level(log::LevelFilter::Info);
 
     info!("rbxlx-to-rojo {}", env!("CARGO_PKG_VERSION"));
 
-    info!("Select a place file.");
-    let file_path = PathBuf::from(match std::env::args().nth(1) {
-        Some(text) => text,
-        None => match nfd::open_file_dialog(Some("rbxl,rbxm,rbxlx,rbxmx"), None)
-            .map_err(|error| Problem::NFDError(error.to_string()))?
-        {
-            nfd::Response::Okay(path) => path,
-            nfd::Response::Cancel => Err(Problem::NFDCancel)?,
-            _ => unreachable!(),
-        },
-    });
+    if arguments.rebuild {
+        return rebuild_place(&arguments);
+    }
+
+    let file_path = match arguments.input.clone() {
+        Some(path) => path,
+        None if arguments.dialogs_allowed() => {
+            info!("Select a place file.");
+            choose_place_file()?
+        }
+        None => {
+            return Err(Problem::Usage(
+                "No place file given, pass one with --input or choose one with --interactive"
+                    .to_owned(),
+            ));
+        }
+    };
+
+    let mut options = arguments.options()?;
 
     info!("Opening place file");
     let file_source = BufReader::new(
@@ -126,19 +517,63 @@ fn routine() -> Result<(), Problem> {
         _ => Err(Problem::InvalidFile),
     }?;
 
-    info!("Select the path to put your Rojo project in.");
-    let root = PathBuf::from(match std::env::args().nth(2) {
-        Some(text) => text,
-        None => match nfd::open_pick_folder(Some(&file_path.parent().unwrap().to_string_lossy()))
-            .map_err(|error| Problem::NFDError(error.to_string()))?
-        {
-            nfd::Response::Okay(path) => path,
-            nfd::Response::Cancel => Err(Problem::NFDCancel)?,
-            _ => unreachable!(),
-        },
-    });
+    // Models become projects rooted at the model, such as a library or plugin
+    let is_model = matches!(
+        file_path.extension().and_then(|extension| extension.to_str()),
+        Some("rbxm") | Some("rbxmx")
+    );
 
-    let mut filesystem = FileSystem::from_root(root.join(file_path.file_stem().unwrap()).into());
+    if is_model && !arguments.as_place {
+        options.project_kind = ProjectKind::Model;
+    }
+
+    let input_folder = file_path
+        .parent()
+        .map_or_else(PathBuf::new, PathBuf::from);
+
+    let root = match arguments.output.clone() {
+        Some(path) => path,
+        None if arguments.dialogs_allowed() => {
+            info!("Select the path to put your Rojo project in.");
+            choose_output_folder(&input_folder)?
+        }
+        None => input_folder,
+    };
+
+    // The folder is always named after the input file, whatever the project is called
+    let folder_name = project_folder_name(&file_path)?;
+
+    // A model project's root is named after the project, so naming it after the model's
+    // top instance keeps that instance's name
+    let top_instance = match options.project_kind {
+        ProjectKind::Model => {
+            model_top_instance(&tree, &options).map_err(Problem::ConversionError)?
+        }
+        ProjectKind::Place => None,
+    };
+
+    let project_name = match (&arguments.project_name, top_instance) {
+        (Some(name), _) => name.clone(),
+        (None, Some(top)) => top.name.clone(),
+        (None, None) => folder_name.to_string_lossy().into_owned(),
+    };
+
+    if arguments.dry_run {
+        info!("Planning the project, nothing will be written...");
+        let mut dry_run = DryRun::new(io::stdout(), project_name);
+        let report = process_instructions_with_options(&tree, &mut dry_run, &options)
+            .map_err(Problem::ConversionError)?;
+        info!("{}", report);
+        return Ok(());
+    }
+
+    let project_folder = root.join(folder_name);
+    let mut filesystem = FileSystem::new(
+        project_folder.clone(),
+        project_name,
+        arguments.output_mode(),
+    )
+    .map_err(Problem::ConversionError)?;
 
     log_file.write().unwrap().replace(
         fs::File::create(root.join("rbxlx-to-rojo.log"))
@@ -146,8 +581,43 @@ fn routine() -> Result<(), Problem> {
     );
 
     info!("Starting processing, please wait a bit...");
-    process_instructions(&tree, &mut filesystem);
-    info!("Done! Check rbxlx-to-rojo.log for a full log.");
+    let report = process_instructions_with_options(&tree, &mut filesystem, &options)
+        .map_err(Problem::ConversionError)?;
+
+    for path in filesystem.stale_files() {
+        if arguments.prune {
+            info!("Deleted {}, which is no longer part of the project", path.display());
+        } else {
+            info!("{} is no longer part of the project", path.display());
+        }
+    }
+
+    for dropped in &report.dropped {
+        debug!("Left out {} ({}) because {}", dropped.instance, dropped.class, dropped.reason);
+    }
+
+    info!("{}", report);
+    fs::write(
+        root.join("report.json"),
+        serde_json::to_string_pretty(&report)
+            .map_err(|error| Problem::ConversionError(error.into()))?,
+    )
+    .map_err(|error| Problem::IoError("write report.json", error))?;
+
+    if arguments.verify {
+        info!("Verifying that every script made it into the project...");
+        let verification =
+            verify(&tree, &project_folder, &report).map_err(Problem::ConversionError)?;
+        info!("{}", verification);
+
+        if !verification.is_ok() {
+            return Err(Problem::VerificationFailed(
+                verification.missing.len() + verification.extra.len() + verification.altered.len(),
+            ));
+        }
+    }
+
+    info!("Done! Check rbxlx-to-rojo.log for a full log, and report.json for the details.");
     Ok(())
 }
 
@@ -155,5 +625,69 @@ fn main() {
     if let Err(error) = routine() {
         eprintln!("An error occurred while using rbxlx-to-rojo.");
         eprintln!("{}", error);
+        process::exit(error.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Arguments, Problem> {
+        Arguments::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_take_values_either_way() {
+        let arguments = parse(&["--input=game.rbxl", "--output", "projects", "--include=A.**"])
+            .expect("couldn't parse arguments");
+        assert_eq!(arguments.input, Some(PathBuf::from("game.rbxl")));
+        assert_eq!(arguments.output, Some(PathBuf::from("projects")));
+        assert_eq!(arguments.include, vec!["A.**".to_owned()]);
+
+        assert!(matches!(parse(&["--input"]), Err(Problem::Usage(_))));
+    }
+
+    #[test]
+    fn switches_dont_take_values() {
+        assert!(parse(&["--force"]).expect("couldn't parse arguments").force);
+        assert!(matches!(parse(&["--force=false"]), Err(Problem::Usage(_))));
+        assert!(matches!(parse(&["--verbose=1"]), Err(Problem::Usage(_))));
+        assert!(matches!(parse(&["--unknown=1"]), Err(Problem::Usage(_))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn positional_paths_fill_in_the_blanks() {
+        let arguments = parse(&["game.rbxl", "projects"]).expect("couldn't parse arguments");
+        assert_eq!(arguments.input, Some(PathBuf::from("game.rbxl")));
+        assert_eq!(arguments.output, Some(PathBuf::from("projects")));
+
+        let arguments =
+            parse(&["--input", "game.rbxl", "projects"]).expect("couldn't parse arguments");
+        assert_eq!(arguments.input, Some(PathBuf::from("game.rbxl")));
+        assert_eq!(arguments.output, Some(PathBuf::from("projects")));
+
+        assert!(matches!(
+            parse(&["game.rbxl", "projects", "extra"]),
+            Err(Problem::Usage(_)),
+        ));
+    }
+
+    #[test]
+    fn conflicting_flags_are_rejected() {
+        for args in [
+            &["--interactive", "--no-dialog"][..],
+            &["--force", "--merge"],
+            &["--prune"],
+            &["--rebuild", "--dry-run"],
+            &["--rebuild", "--merge"],
+            &["--verify", "--dry-run"],
+            &["--verify", "--rebuild"],
+        ] {
+            assert!(matches!(parse(args), Err(Problem::Usage(_))), "{:?} was accepted", args);
+        }
+
+        assert!(parse(&["--merge", "--prune"]).is_ok());
+        assert!(parse(&["--rebuild", "--force"]).is_ok());
+    }
+}
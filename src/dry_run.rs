@@ -0,0 +1,116 @@
+use crate::{
+    error::ConversionError,
+    filesystem::{Project, SRC},
+    structures::*,
+};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Prints what a FileSystem would write instead of writing it: the file tree with the size
+// of every file, followed by default.project.json
+pub struct DryRun<W: Write> {
+    output: W,
+    project: Project,
+    // Folders have no size
+    entries: BTreeMap<PathBuf, Option<usize>>,
+}
+
+impl<W: Write> DryRun<W> {
+    pub fn new(output: W, name: impl Into<String>) -> Self {
+        Self {
+            output,
+            project: Project::new(name.into()),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn print(&mut self) -> std::io::Result<()> {
+        let total: usize = self.entries.values().flatten().sum();
+        let files = self.entries.values().flatten().count();
+
+        writeln!(self.output, "{}/ ({} files, {})", SRC, files, format_size(total))?;
+
+        for (path, size) in &self.entries {
+            let depth = path.components().count();
+            let name = path.file_name().map_or_else(
+                || path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            );
+
+            match size {
+                Some(size) => writeln!(
+                    self.output,
+                    "{:indent$}{} ({})",
+                    "",
+                    name,
+                    format_size(*size),
+                    indent = depth * 2,
+                )?,
+                None => writeln!(self.output, "{:indent$}{}/", "", name, indent = depth * 2)?,
+            }
+        }
+
+        writeln!(self.output)?;
+        writeln!(self.output, "default.project.json")?;
+        writeln!(
+            self.output,
+            "{}",
+            serde_json::to_string_pretty(&self.project).map_err(std::io::Error::other)?
+        )?;
+        self.output.flush()
+    }
+}
+
+impl<W: Write> InstructionReader for DryRun<W> {
+    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) -> Result<(), ConversionError> {
+        match instruction {
+            Instruction::AddToTree { name, partition } => {
+                self.project.add_to_tree(name, partition)?;
+            }
+
+            Instruction::CreateFile { filename, contents } => {
+                self.entries
+                    .insert(filename.into_owned(), Some(contents.len()));
+            }
+
+            Instruction::CreateFolder { folder } => {
+                // Parent folders are created along with their children, like create_dir_all does
+                for ancestor in folder.ancestors().filter(|path| *path != Path::new("")) {
+                    self.entries.entry(ancestor.to_path_buf()).or_insert(None);
+                }
+            }
+
+            Instruction::ModelRoot => self.project.root_at_source()?,
+        }
+
+        Ok(())
+    }
+
+    fn finish_instructions(&mut self) -> Result<(), ConversionError> {
+        self.print().map_err(ConversionError::PrintError)
+    }
+}
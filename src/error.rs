@@ -0,0 +1,111 @@
+use rbx_dom_weak::types::Ref;
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum ConversionError {
+    BinaryDecodeError(PathBuf, rbx_binary::DecodeError),
+    BinaryEncodeError(String, rbx_binary::EncodeError),
+    DuplicateTreeEntry(String),
+    InvalidJson(PathBuf, serde_json::Error),
+    InvalidLocalizationTable(String),
+    InvalidProperty(PathBuf, String),
+    InvalidSource(String),
+    IoError(&'static str, PathBuf, io::Error),
+    JsonError(serde_json::Error),
+    MissingInstance(Ref),
+    MissingSource(String),
+    NameCollisions(Vec<String>),
+    OutputNotEmpty(PathBuf),
+    PrintError(io::Error),
+    XmlDecodeError(PathBuf, rbx_xml::DecodeError),
+    XmlEncodeError(String, rbx_xml::EncodeError),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::BinaryDecodeError(path, error) => {
+                write!(formatter, "Couldn't decode the binary model {}: {}", path.display(), error)
+            }
+
+            ConversionError::BinaryEncodeError(name, error) => {
+                write!(formatter, "Couldn't encode {} as a binary model: {}", name, error)
+            }
+
+            ConversionError::DuplicateTreeEntry(name) => write!(
+                formatter,
+                "Duplicate item added to tree! Instances can't have the same name: {}",
+                name,
+            ),
+
+            ConversionError::InvalidJson(path, error) => {
+                write!(formatter, "Couldn't read {}: {}", path.display(), error)
+            }
+
+            ConversionError::InvalidLocalizationTable(name) => {
+                write!(formatter, "The Contents of {} is not a valid localization table", name)
+            }
+
+            ConversionError::InvalidProperty(path, key) => write!(
+                formatter,
+                "{} has a value for {} that can't be read",
+                path.display(),
+                key,
+            ),
+
+            ConversionError::InvalidSource(name) => {
+                write!(formatter, "The Source of {} is not a string", name)
+            }
+
+            ConversionError::IoError(doing_what, path, error) => write!(
+                formatter,
+                "While attempting to {} {}, {}",
+                doing_what,
+                path.display(),
+                error,
+            ),
+
+            ConversionError::JsonError(error) => {
+                write!(formatter, "Couldn't serialize JSON: {}", error)
+            }
+
+            ConversionError::MissingInstance(referent) => {
+                write!(formatter, "The place refers to an instance that doesn't exist: {:?}", referent)
+            }
+
+            ConversionError::MissingSource(name) => {
+                write!(formatter, "{} is a script, but has no Source", name)
+            }
+
+            ConversionError::NameCollisions(names) => write!(
+                formatter,
+                "These instances have the same name as one of their siblings: {}",
+                names.join(", "),
+            ),
+
+            ConversionError::OutputNotEmpty(path) => {
+                write!(formatter, "{} already has files in it", path.display())
+            }
+
+            ConversionError::PrintError(error) => {
+                write!(formatter, "Couldn't print the planned project: {}", error)
+            }
+
+            ConversionError::XmlDecodeError(path, error) => {
+                write!(formatter, "Couldn't decode the XML model {}: {}", path.display(), error)
+            }
+
+            ConversionError::XmlEncodeError(name, error) => {
+                write!(formatter, "Couldn't encode {} as an XML model: {}", name, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<serde_json::Error> for ConversionError {
+    fn from(error: serde_json::Error) -> Self {
+        ConversionError::JsonError(error)
+    }
+}
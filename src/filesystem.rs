@@ -1,115 +1,278 @@
-use crate::structures::*;
+use crate::{error::ConversionError, structures::*};
 use serde::{ser::SerializeMap, Serialize, Serializer};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    io::{self, Write},
+    path::{Path, PathBuf},
 };
 
-const SRC: &str = "src";
+pub(crate) const SRC: &str = "src";
+
+#[derive(Clone, Debug)]
+enum ProjectTree {
+    // A DataModel with the services added to it
+    Place(BTreeMap<String, TreePartition>),
+    // Whatever the source folder is, which says what class it is through its init files
+    Model,
+}
 
 fn serialize_project_tree<S: Serializer>(
-    tree: &BTreeMap<String, TreePartition>,
+    tree: &ProjectTree,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    let mut map = serializer.serialize_map(Some(tree.len() + 1))?;
-    map.serialize_entry("$className", "DataModel")?;
-    for (k, v) in tree {
-        map.serialize_entry(k, v)?;
+    match tree {
+        ProjectTree::Place(tree) => {
+            let mut map = serializer.serialize_map(Some(tree.len() + 1))?;
+            map.serialize_entry("$className", "DataModel")?;
+            for (k, v) in tree {
+                map.serialize_entry(k, v)?;
+            }
+            map.end()
+        }
+
+        ProjectTree::Model => {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry("$path", SRC)?;
+            map.end()
+        }
     }
-    map.end()
 }
 
+// The contents of default.project.json
 #[derive(Clone, Debug, Serialize)]
-struct Project {
+pub(crate) struct Project {
     name: String,
     #[serde(serialize_with = "serialize_project_tree")]
-    tree: BTreeMap<String, TreePartition>,
+    tree: ProjectTree,
 }
 
 impl Project {
-    fn new() -> Self {
+    pub(crate) fn new(name: String) -> Self {
         Self {
-            name: "project".to_string(),
-            tree: BTreeMap::new(),
+            name,
+            tree: ProjectTree::Place(BTreeMap::new()),
+        }
+    }
+
+    // A model's top instance takes up the whole tree, so nothing can be added to it before or after
+    pub(crate) fn root_at_source(&mut self) -> Result<(), ConversionError> {
+        if let ProjectTree::Place(tree) = &self.tree
+            && let Some(name) = tree.keys().next()
+        {
+            return Err(ConversionError::DuplicateTreeEntry(name.clone()));
+        }
+
+        self.tree = ProjectTree::Model;
+        Ok(())
+    }
+
+    // Paths in instructions are relative to the source folder, but the project's are relative to the project
+    pub(crate) fn add_to_tree(
+        &mut self,
+        name: String,
+        mut partition: TreePartition,
+    ) -> Result<(), ConversionError> {
+        let ProjectTree::Place(tree) = &mut self.tree else {
+            return Err(ConversionError::DuplicateTreeEntry(name));
+        };
+
+        if tree.contains_key(&name) {
+            return Err(ConversionError::DuplicateTreeEntry(name));
+        }
+
+        if let Some(path) = partition.path {
+            partition.path = Some(PathBuf::from(SRC).join(path));
         }
+
+        for child in partition.children.values_mut() {
+            if let Some(path) = &child.path {
+                child.path = Some(PathBuf::from(SRC).join(path));
+            }
+        }
+
+        tree.insert(name, partition);
+        Ok(())
     }
 }
 
+// What to do when the project folder already has something in it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    // Refuse to write into it
+    #[default]
+    Fresh,
+    // Delete everything in it first
+    Force,
+    // Only write files whose contents changed, and keep track of the files
+    // the conversion didn't produce, deleting them at the end if prune is set
+    Merge { prune: bool },
+}
+
 #[derive(Clone, Debug)]
 pub struct FileSystem {
+    mode: OutputMode,
     project: Project,
     root: PathBuf,
     source: PathBuf,
+    stale_files: BTreeSet<PathBuf>,
+    stale_folders: BTreeSet<PathBuf>,
+}
+
+// Symlinks are collected like files and never followed, so pruning only ever removes the link
+fn collect_existing(
+    folder: &Path,
+    files: &mut BTreeSet<PathBuf>,
+    folders: &mut BTreeSet<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_existing(&path, files, folders)?;
+            folders.insert(path);
+        } else {
+            files.insert(path);
+        }
+    }
+
+    Ok(())
 }
 
 impl FileSystem {
-    pub fn from_root(root: PathBuf) -> Self {
+    // Writes a project named "project" into an empty or missing folder
+    pub fn from_root(root: PathBuf) -> Result<Self, ConversionError> {
+        Self::new(root, "project", OutputMode::Fresh)
+    }
+
+    pub fn new(
+        root: PathBuf,
+        name: impl Into<String>,
+        mode: OutputMode,
+    ) -> Result<Self, ConversionError> {
         let source = root.join(SRC);
-        let project = Project::new();
+        let mut stale_files = BTreeSet::new();
+        let mut stale_folders = BTreeSet::new();
 
-        fs::create_dir(&source).ok(); // It'll error later if it matters
+        if root.exists() {
+            match mode {
+                OutputMode::Fresh => {
+                    let mut entries = fs::read_dir(&root).map_err(|error| {
+                        ConversionError::IoError("read folder", root.clone(), error)
+                    })?;
 
-        Self {
-            project,
+                    if entries.next().is_some() {
+                        return Err(ConversionError::OutputNotEmpty(root));
+                    }
+                }
+
+                OutputMode::Force => fs::remove_dir_all(&root).map_err(|error| {
+                    ConversionError::IoError("remove folder", root.clone(), error)
+                })?,
+
+                // Only what the converter writes is tracked, so anything else kept in the
+                // project folder, like .git or README.md, is left alone
+                OutputMode::Merge { .. } => {
+                    if fs::symlink_metadata(&source).is_ok_and(|metadata| metadata.is_dir()) {
+                        collect_existing(&source, &mut stale_files, &mut stale_folders).map_err(
+                            |error| ConversionError::IoError("read folder", source.clone(), error),
+                        )?;
+                    }
+
+                    let project_file = root.join("default.project.json");
+                    if project_file.is_file() {
+                        stale_files.insert(project_file);
+                    }
+                }
+            }
+        }
+
+        fs::create_dir_all(&source)
+            .map_err(|error| ConversionError::IoError("create folder", source.clone(), error))?;
+        stale_folders.remove(&source);
+
+        Ok(Self {
+            mode,
+            project: Project::new(name.into()),
             root,
             source,
+            stale_files,
+            stale_folders,
+        })
+    }
+
+    // Files left over from an earlier conversion that this one didn't produce.
+    // Only OutputMode::Merge finds any, and they're already deleted if it prunes.
+    pub fn stale_files(&self) -> &BTreeSet<PathBuf> {
+        &self.stale_files
+    }
+
+    fn write_file(&mut self, path: PathBuf, contents: &[u8]) -> Result<(), ConversionError> {
+        let existed = self.stale_files.remove(&path);
+
+        // Leaving unchanged files alone keeps their modification times, which editors and Rojo watch
+        if existed && fs::read(&path).is_ok_and(|existing| existing == contents) {
+            return Ok(());
         }
+
+        let mut file = File::create(&path)
+            .map_err(|error| ConversionError::IoError("create file", path.clone(), error))?;
+        file.write_all(contents)
+            .map_err(|error| ConversionError::IoError("write to file", path, error))
+    }
+
+    fn prune(&mut self) -> Result<(), ConversionError> {
+        for path in &self.stale_files {
+            fs::remove_file(path)
+                .map_err(|error| ConversionError::IoError("remove file", path.clone(), error))?;
+        }
+
+        // Reversed so that folders are removed before the folders they're in
+        for path in self.stale_folders.iter().rev() {
+            fs::remove_dir(path)
+                .map_err(|error| ConversionError::IoError("remove folder", path.clone(), error))?;
+        }
+
+        Ok(())
     }
 }
 
 impl InstructionReader for FileSystem {
-    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) {
+    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) -> Result<(), ConversionError> {
         match instruction {
-            Instruction::AddToTree {
-                name,
-                mut partition,
-            } => {
-                assert!(
-                    self.project.tree.get(&name).is_none(),
-                    "Duplicate item added to tree! Instances can't have the same name: {}",
-                    name
-                );
-
-                if let Some(path) = partition.path {
-                    partition.path = Some(PathBuf::from(SRC).join(path));
-                }
-
-                for child in partition.children.values_mut() {
-                    if let Some(path) = &child.path {
-                        child.path = Some(PathBuf::from(SRC).join(path));
-                    }
-                }
-
-                self.project.tree.insert(name, partition);
+            Instruction::AddToTree { name, partition } => {
+                self.project.add_to_tree(name, partition)?;
             }
 
             Instruction::CreateFile { filename, contents } => {
-                let mut file = File::create(self.source.join(&filename)).unwrap_or_else(|error| {
-                    panic!("can't create file {:?}: {:?}", filename, error)
-                });
-                file.write_all(&contents).unwrap_or_else(|error| {
-                    panic!("can't write to file {:?} due to {:?}", filename, error)
-                });
+                self.write_file(self.source.join(&filename), &contents)?;
             }
 
             Instruction::CreateFolder { folder } => {
-                fs::create_dir_all(self.source.join(&folder)).unwrap_or_else(|error| {
-                    panic!("can't write to folder {:?}: {:?}", folder, error)
-                });
+                let path = self.source.join(&folder);
+                fs::create_dir_all(&path)
+                    .map_err(|error| ConversionError::IoError("create folder", path.clone(), error))?;
+
+                // Every folder up to the source folder is in use now
+                for ancestor in path.ancestors().take_while(|ancestor| *ancestor != self.source) {
+                    self.stale_folders.remove(ancestor);
+                }
             }
+
+            Instruction::ModelRoot => self.project.root_at_source()?,
         }
+
+        Ok(())
     }
 
-    fn finish_instructions(&mut self) {
-        let mut file = File::create(self.root.join("default.project.json"))
-            .expect("can't create default.project.json");
-        file.write_all(
-            &serde_json::to_string_pretty(&self.project)
-                .expect("couldn't serialize project")
-                .as_bytes(),
-        )
-        .expect("can't write project");
+    fn finish_instructions(&mut self) -> Result<(), ConversionError> {
+        let contents = serde_json::to_string_pretty(&self.project)?;
+        self.write_file(self.root.join("default.project.json"), contents.as_bytes())?;
+
+        if self.mode == (OutputMode::Merge { prune: true }) {
+            self.prune()?;
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,98 @@
+use crate::{error::ConversionError, get_instance, structures::Options};
+use rbx_dom_weak::{types::Ref, Instance, WeakDom};
+use std::collections::HashSet;
+
+// Whether one name matches one segment of a pattern, where * matches any run of characters
+fn matches_segment(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => name.strip_prefix(prefix).is_some_and(|name| {
+            (0..=name.len())
+                .filter(|index| name.is_char_boundary(*index))
+                .any(|index| matches_segment(rest, &name[index..]))
+        }),
+    }
+}
+
+// A ** segment matches any number of names, including none
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..])),
+        Some((segment, rest)) => path.split_first().is_some_and(|(name, names)| {
+            matches_segment(segment, name) && matches_segments(rest, names)
+        }),
+    }
+}
+
+// Patterns are full instance paths split by dots, such as ReplicatedStorage.Shared.**
+// or Workspace.**.Plugins_*
+fn matches(pattern: &str, path: &[&str]) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    matches_segments(&pattern, path)
+}
+
+// Returns whether anything in the instance's subtree is kept
+fn visit<'a>(
+    tree: &'a WeakDom,
+    instance: &'a Instance,
+    path: &mut Vec<&'a str>,
+    ancestor_included: bool,
+    options: &Options,
+    filtered_out: &mut HashSet<Ref>,
+) -> Result<bool, ConversionError> {
+    path.push(instance.name.as_str());
+
+    let kept = if options.exclude.iter().any(|pattern| matches(pattern, path)) {
+        false
+    } else {
+        let included =
+            ancestor_included || options.include.iter().any(|pattern| matches(pattern, path));
+
+        let mut descendant_kept = false;
+        for child_id in instance.children() {
+            let child = get_instance(tree, *child_id)?;
+            descendant_kept |= visit(tree, child, path, included, options, filtered_out)?;
+        }
+
+        included || descendant_kept
+    };
+
+    path.pop();
+
+    if !kept {
+        filtered_out.insert(instance.referent());
+    }
+
+    Ok(kept)
+}
+
+// Finds the instances Options::include and Options::exclude leave out of the conversion.
+// An instance is kept if it or an ancestor matches an include pattern (or there are none),
+// or if it's the ancestor of an instance that is kept, unless it or an ancestor matches
+// an exclude pattern.
+pub(crate) fn filtered_out(
+    tree: &WeakDom,
+    options: &Options,
+) -> Result<HashSet<Ref>, ConversionError> {
+    let mut filtered_out = HashSet::new();
+    if options.include.is_empty() && options.exclude.is_empty() {
+        return Ok(filtered_out);
+    }
+
+    let root = get_instance(tree, tree.root_ref())?;
+    let mut path = Vec::new();
+    for child_id in root.children() {
+        let child = get_instance(tree, *child_id)?;
+        visit(
+            tree,
+            child,
+            &mut path,
+            options.include.is_empty(),
+            options,
+            &mut filtered_out,
+        )?;
+    }
+
+    Ok(filtered_out)
+}
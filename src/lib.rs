@@ -1,170 +1,723 @@
-use log::debug;
+use log::{debug, warn};
 use rbx_dom_weak::{
     types::{Ref, Variant},
     Ustr,
-    Instance, WeakDom,
+    Instance, InstanceBuilder, WeakDom,
 };
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 use rbx_reflection_database::get;
 
+use error::ConversionError;
+use filters::filtered_out;
+use names::FileNames;
+use properties::{
+    encode_attributes, encode_tags, encode_value, non_default_properties, unexported_properties,
+};
+use report::{DropReason, Dropped, Report, UnexportedProperty};
 use structures::*;
 
+pub mod dry_run;
+pub mod error;
 pub mod filesystem;
+mod filters;
+mod localization;
+mod names;
+mod properties;
+pub mod rebuild;
+pub mod report;
 pub mod structures;
+pub mod verify;
 
 #[cfg(test)]
 mod tests;
 
-lazy_static::lazy_static! {
-    static ref NON_TREE_SERVICES: HashSet<&'static str> = include_str!("./non-tree-services.txt").lines().collect();
-    static ref RESPECTED_SERVICES: HashSet<&'static str> = include_str!("./respected-services.txt").lines().collect();
-}
+type Representation<'a> = Option<(Vec<Instruction<'a>>, Option<Cow<'a, Path>>)>;
 
 struct TreeIterator<'a, I: InstructionReader + ?Sized> {
+    file_names: &'a FileNames,
+    // Instances left out by Options::include and Options::exclude
+    filtered_out: &'a HashSet<Ref>,
     instruction_reader: &'a mut I,
+    options: &'a Options,
     path: &'a Path,
+    report: &'a mut Report,
+    // The service being visited, empty while visiting the services themselves
+    service: &'a str,
     tree: &'a WeakDom,
 }
 
+// Why repr_instance left an instance out, if anything was lost by it
+fn drop_reason(
+    child: &Instance,
+    has_scripts: &HashMap<Ref, bool>,
+    options: &Options,
+) -> Option<DropReason> {
+    let class = child.class.as_str();
+
+    // Empty services are left out too, but there's nothing in them to lose
+    if child.children().is_empty() && is_service(class, options) {
+        return None;
+    }
+
+    if is_service(class, options) && !options.respected_services.contains(class) {
+        Some(DropReason::ServiceNotRespected)
+    } else if has_scripts.get(&child.referent()) == Some(&true) {
+        None
+    } else if options.non_script_instances == NonScriptInstances::Drop {
+        Some(DropReason::NoScripts)
+    } else if !is_creatable(class) {
+        Some(DropReason::NotCreatable)
+    } else {
+        None
+    }
+}
+
+fn count_script(report: &mut Report, instance: &Instance) {
+    if !matches!(instance.class.as_str(), "Script" | "LocalScript" | "ModuleScript") {
+        return;
+    }
+
+    report.scripts += 1;
+    *report
+        .scripts_by_class
+        .entry(instance.class.to_string())
+        .or_default() += 1;
+
+    if let Some(Variant::String(source)) = instance.properties.get(&Ustr::from("Source")) {
+        report.source_bytes += source.len();
+    }
+}
+
+// Model files keep every property of what's in them, anything else has its properties written
+// to a meta file or the project, which can't hold all of them. Only properties that would have
+// been exported are recorded
+fn record_unexported(
+    report: &mut Report,
+    tree: &WeakDom,
+    instance: &Instance,
+    instructions: &[Instruction],
+    options: &Options,
+) {
+    let exported = if is_service(&instance.class, options) {
+        options.service_properties && !options.non_tree_services.contains(instance.class.as_str())
+    } else {
+        options.export_properties
+    };
+
+    if !exported {
+        return;
+    }
+
+    let in_model_file = instructions.iter().any(|instruction| match instruction {
+        Instruction::CreateFile { filename, .. } => {
+            let filename = filename.to_string_lossy();
+            [".rbxmx", ".rbxm", ".model.json"]
+                .iter()
+                .any(|extension| filename.ends_with(extension))
+        }
+        _ => false,
+    });
+
+    if in_model_file {
+        return;
+    }
+
+    for property in unexported_properties(instance) {
+        let instance = full_name(tree, instance);
+        warn!("{}.{} couldn't be exported, Rojo can't represent its value", instance, property);
+        report
+            .unexported_properties
+            .push(UnexportedProperty { instance, property });
+    }
+}
+
+fn count_by_class(
+    tree: &WeakDom,
+    instance: &Instance,
+    counts: &mut BTreeMap<String, usize>,
+) -> Result<usize, ConversionError> {
+    *counts.entry(instance.class.to_string()).or_default() += 1;
+
+    let mut total = 1;
+    for child_id in instance.children() {
+        total += count_by_class(tree, get_instance(tree, *child_id)?, counts)?;
+    }
+
+    Ok(total)
+}
+
+pub(crate) fn get_instance(tree: &WeakDom, referent: Ref) -> Result<&Instance, ConversionError> {
+    tree.get_by_ref(referent)
+        .ok_or(ConversionError::MissingInstance(referent))
+}
+
+// The path of an instance from the root, such as Workspace.Map.Tree
+pub(crate) fn full_name(tree: &WeakDom, instance: &Instance) -> String {
+    let mut names = vec![instance.name.as_str()];
+    let mut parent = tree.get_by_ref(instance.parent());
+
+    while let Some(ancestor) = parent {
+        if ancestor.referent() == tree.root_ref() {
+            break;
+        }
+
+        names.push(ancestor.name.as_str());
+        parent = tree.get_by_ref(ancestor.parent());
+    }
+
+    names.reverse();
+    names.join(".")
+}
+
+pub(crate) fn is_represented(
+    referent: Ref,
+    has_scripts: &HashMap<Ref, bool>,
+    filtered_out: &HashSet<Ref>,
+    options: &Options,
+) -> bool {
+    !filtered_out.contains(&referent)
+        && (options.non_script_instances != NonScriptInstances::Drop
+            || has_scripts.get(&referent) == Some(&true))
+}
+
+// Instances whose name on disk differs from their real name keep it through their meta file
+fn name_override(child: &Instance, name: &str) -> Option<String> {
+    if child.name == name {
+        None
+    } else {
+        Some(child.name.clone())
+    }
+}
+
+pub(crate) fn is_service(class: &str, options: &Options) -> bool {
+    options.respected_services.contains(class)
+        || get().classes.get(class).is_some_and(|reflected| {
+            reflected
+                .tags
+                .iter()
+                .any(|tag| format!("{:?}", tag) == "Service")
+        })
+}
+
+// Rojo can't instantiate classes like Terrain from a model file, since nothing can create them
+fn is_creatable(class: &str) -> bool {
+    get().classes.get(class).is_none_or(|reflected| {
+        !reflected
+            .tags
+            .iter()
+            .any(|tag| format!("{:?}", tag) == "NotCreatable")
+    })
+}
+
+fn meta_contents<'a>(meta: &MetaFile) -> Result<Cow<'a, [u8]>, ConversionError> {
+    Ok(Cow::Owned(serde_json::to_string_pretty(meta)?.into_bytes()))
+}
+
+fn properties_for(instance: &Instance, options: &Options) -> BTreeMap<String, serde_json::Value> {
+    if options.export_properties {
+        non_default_properties(instance)
+    } else {
+        BTreeMap::new()
+    }
+}
+
+// Services only live in the project, so their properties go there unless that's turned off
+fn service_properties_for(
+    service: &Instance,
+    options: &Options,
+) -> BTreeMap<String, serde_json::Value> {
+    if options.service_properties {
+        non_default_properties(service)
+    } else {
+        BTreeMap::new()
+    }
+}
+
+fn meta_file(child: &Instance, name: &str, options: &Options) -> MetaFile {
+    let mut properties = properties_for(child, options);
+    if let Some(tags) = encode_tags(child) {
+        properties.insert("Tags".to_owned(), tags);
+    }
+
+    let attributes = encode_attributes(child).unwrap_or_else(|| {
+        warn!("{} has attributes that can't be written to a meta file", child.name);
+        BTreeMap::new()
+    });
+
+    MetaFile {
+        class_name: None,
+        name: name_override(child, name),
+        properties,
+        attributes,
+        ignore_unknown_instances: true,
+    }
+}
+
+fn json_model(tree: &WeakDom, instance: &Instance) -> Option<JsonModel> {
+    let mut properties = BTreeMap::new();
+
+    for (key, value) in &instance.properties {
+        match value {
+            // Rojo generates these itself
+            Variant::UniqueId(_) => continue,
+            Variant::Ref(referent) if referent.is_none() => continue,
+            // These are handled below, since they have their own format
+            Variant::Tags(_) | Variant::Attributes(_) => continue,
+            _ => {}
+        }
+
+        properties.insert(key.to_string(), encode_value(value)?);
+    }
+
+    if let Some(tags) = encode_tags(instance) {
+        properties.insert("Tags".to_owned(), tags);
+    }
+
+    let children = instance
+        .children()
+        .iter()
+        .map(|child_id| {
+            let child = tree.get_by_ref(*child_id)?;
+            let mut model = json_model(tree, child)?;
+            model.name = Some(child.name.clone());
+            Some(model)
+        })
+        .collect::<Option<_>>()?;
+
+    Some(JsonModel {
+        name: None,
+        class_name: instance.class.to_string(),
+        properties,
+        attributes: encode_attributes(instance)?,
+        children,
+    })
+}
+
+// Errors name the instance as it is in the original tree, since the model can be a copy
+fn write_rbxmx(
+    tree: &WeakDom,
+    model: &Instance,
+    original: &WeakDom,
+    child: &Instance,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut contents = Vec::new();
+    rbx_xml::to_writer_default(&mut contents, tree, &[model.referent()]).map_err(|error| {
+        ConversionError::XmlEncodeError(full_name(original, child), error)
+    })?;
+    Ok(contents)
+}
+
+fn copy_kept(
+    tree: &WeakDom,
+    instance: &Instance,
+    filtered_out: &HashSet<Ref>,
+    copy: &mut WeakDom,
+    parent: Ref,
+    copied: &mut HashMap<Ref, Ref>,
+) -> Result<(), ConversionError> {
+    let builder = InstanceBuilder::new(instance.class.as_str())
+        .with_name(instance.name.as_str())
+        .with_properties(instance.properties.clone());
+    let referent = copy.insert(parent, builder);
+    copied.insert(instance.referent(), referent);
+
+    for child_id in instance.children() {
+        if !filtered_out.contains(child_id) {
+            let child = get_instance(tree, *child_id)?;
+            copy_kept(tree, child, filtered_out, copy, referent, copied)?;
+        }
+    }
+
+    Ok(())
+}
+
+// A model file holds the instance's whole subtree, so if include or exclude left out anything
+// in it, the model is written from a copy without them. Refs to instances outside the copy are
+// cleared, the same as refs to instances outside a model always are.
+fn without_filtered(
+    tree: &WeakDom,
+    instance: &Instance,
+    filtered_out: &HashSet<Ref>,
+) -> Result<Option<WeakDom>, ConversionError> {
+    if !tree
+        .descendants_of(instance.referent())
+        .any(|descendant| filtered_out.contains(&descendant.referent()))
+    {
+        return Ok(None);
+    }
+
+    let mut copy = WeakDom::new(InstanceBuilder::new("DataModel"));
+    let mut copied = HashMap::new();
+    let root = copy.root_ref();
+    copy_kept(tree, instance, filtered_out, &mut copy, root, &mut copied)?;
+
+    for referent in copied.values() {
+        let Some(instance) = copy.get_by_ref_mut(*referent) else {
+            continue;
+        };
+
+        for value in instance.properties.values_mut() {
+            if let Variant::Ref(target) = value {
+                *target = copied.get(target).copied().unwrap_or_else(Ref::none);
+            }
+        }
+    }
+
+    Ok(Some(copy))
+}
+
+fn repr_model<'a>(
+    base: &'a Path,
+    child: &'a Instance,
+    name: &str,
+    tree: &WeakDom,
+    filtered_out: &HashSet<Ref>,
+    format: NonScriptInstances,
+) -> Result<Representation<'a>, ConversionError> {
+    if format == NonScriptInstances::Drop {
+        return Ok(None);
+    }
+
+    let copy = without_filtered(tree, child, filtered_out)?;
+    let (model_tree, model) = match &copy {
+        Some(copy) => (copy, get_instance(copy, copy.root().children()[0])?),
+        None => (tree, child),
+    };
+
+    let (contents, extension) = match format {
+        NonScriptInstances::Drop => return Ok(None),
+
+        NonScriptInstances::Rbxmx => (write_rbxmx(model_tree, model, tree, child)?, "rbxmx"),
+
+        NonScriptInstances::Rbxm => {
+            let mut contents = Vec::new();
+            rbx_binary::to_writer(&mut contents, model_tree, &[model.referent()]).map_err(
+                |error| ConversionError::BinaryEncodeError(full_name(tree, child), error),
+            )?;
+            (contents, "rbxm")
+        }
+
+        NonScriptInstances::ModelJson => match json_model(model_tree, model) {
+            Some(model) => (serde_json::to_string_pretty(&model)?.into_bytes(), "model.json"),
+
+            None => {
+                debug!("{} can't be represented as JSON, using rbxmx instead", child.name);
+                (write_rbxmx(model_tree, model, tree, child)?, "rbxmx")
+            }
+        },
+    };
+
+    let mut instructions = vec![Instruction::CreateFile {
+        filename: Cow::Owned(base.join(format!("{}.{}", name, extension))),
+        contents: Cow::Owned(contents),
+    }];
+
+    let meta = MetaFile {
+        class_name: None,
+        name: name_override(child, name),
+        properties: BTreeMap::new(),
+        attributes: BTreeMap::new(),
+        ignore_unknown_instances: true,
+    };
+
+    if meta.has_contents() {
+        instructions.push(Instruction::CreateFile {
+            filename: Cow::Owned(base.join(format!("{}.meta.json", name))),
+            contents: meta_contents(&meta)?,
+        });
+    }
+
+    // The whole subtree lives in the model file, so there's nothing to descend into
+    Ok(Some((instructions, None)))
+}
+
+// The extension of the Rojo file type that can hold this instance by itself, if any
+fn native_file_type(instance: &Instance, options: &Options) -> Option<&'static str> {
+    if !options.native_file_types || !instance.children().is_empty() {
+        return None;
+    }
+
+    match instance.class.as_str() {
+        "StringValue" => Some("txt"),
+        "LocalizationTable" => Some("csv"),
+        _ => None,
+    }
+}
+
+fn repr_native_file<'a>(
+    base: &'a Path,
+    child: &'a Instance,
+    name: &str,
+    extension: &str,
+    tree: &WeakDom,
+    options: &Options,
+) -> Result<Representation<'a>, ConversionError> {
+    let string_property = |key: &str| match child.properties.get(&Ustr::from(key)) {
+        Some(Variant::String(value)) => value.clone(),
+        _ => String::new(),
+    };
+
+    // The file itself is the value, so it shouldn't be repeated in the meta file
+    let (contents, value_property) = match child.class.as_str() {
+        "LocalizationTable" => (
+            localization::to_csv(&string_property("Contents"))
+                .ok_or_else(|| ConversionError::InvalidLocalizationTable(full_name(tree, child)))?,
+            "Contents",
+        ),
+        _ => (string_property("Value"), "Value"),
+    };
+
+    let mut instructions = vec![Instruction::CreateFile {
+        filename: Cow::Owned(base.join(format!("{}.{}", name, extension))),
+        contents: Cow::Owned(contents.into_bytes()),
+    }];
+
+    let mut meta = meta_file(child, name, options);
+    meta.properties.remove(value_property);
+
+    if meta.has_contents() {
+        instructions.push(Instruction::CreateFile {
+            filename: Cow::Owned(base.join(format!("{}.meta.json", name))),
+            contents: meta_contents(&meta)?,
+        });
+    }
+
+    Ok(Some((instructions, None)))
+}
+
+// The extension a script is written with, such as .server.lua
+fn script_extension(child: &Instance, options: &Options) -> String {
+    format!(
+        "{}.{}",
+        match child.class.as_str() {
+            "Script" => ".server",
+            "LocalScript" => ".client",
+            _ => "",
+        },
+        options.script_extension.as_str(),
+    )
+}
+
+// The extensions of the files repr_instance writes an instance as, or none if it's a folder.
+// A model written as JSON falls back to rbxmx when it can't be, so it could be either.
+pub(crate) fn file_extensions(
+    child: &Instance,
+    has_scripts: &HashMap<Ref, bool>,
+    filtered_out: &HashSet<Ref>,
+    options: &Options,
+) -> Vec<String> {
+    if let Some(extension) = native_file_type(child, options) {
+        return vec![format!(".{}", extension)];
+    }
+
+    if has_scripts.get(&child.referent()) != Some(&true) {
+        if is_service(child.class.as_str(), options) {
+            return Vec::new();
+        }
+
+        return match options.non_script_instances {
+            NonScriptInstances::Drop => Vec::new(),
+            NonScriptInstances::Rbxmx => vec![".rbxmx".to_owned()],
+            NonScriptInstances::Rbxm => vec![".rbxm".to_owned()],
+            NonScriptInstances::ModelJson => vec![".model.json".to_owned(), ".rbxmx".to_owned()],
+        };
+    }
+
+    match child.class.as_str() {
+        "Script" | "LocalScript" | "ModuleScript"
+            if !child
+                .children()
+                .iter()
+                .any(|id| is_represented(*id, has_scripts, filtered_out, options)) =>
+        {
+            vec![script_extension(child, options)]
+        }
+
+        _ => Vec::new(),
+    }
+}
+
+fn script_source<'a>(tree: &WeakDom, child: &'a Instance) -> Result<&'a [u8], ConversionError> {
+    match child.properties.get(&Ustr::from("Source")) {
+        Some(Variant::String(value)) => Ok(value.as_bytes()),
+        Some(_) => Err(ConversionError::InvalidSource(full_name(tree, child))),
+        None => Err(ConversionError::MissingSource(full_name(tree, child))),
+    }
+}
+
+fn script_meta(child: &Instance, name: &str, options: &Options) -> MetaFile {
+    let mut meta = meta_file(child, name, options);
+
+    // Rojo makes .server files into Scripts with the legacy run context,
+    // so any other run context has to come from the meta file
+    if child.class.as_str() == "Script"
+        && let Some(run_context @ Variant::Enum(value)) =
+            child.properties.get(&Ustr::from("RunContext"))
+        && value.to_u32() != 0
+        && let Some(encoded) = encode_value(run_context)
+    {
+        meta.properties.insert("RunContext".to_owned(), encoded);
+    }
+
+    // Without these, disabled scripts would come back enabled
+    if matches!(child.class.as_str(), "Script" | "LocalScript") {
+        for (key, running) in [("Disabled", false), ("Enabled", true)] {
+            if let Some(Variant::Bool(value)) = child.properties.get(&Ustr::from(key))
+                && *value != running
+            {
+                meta.properties.insert(key.to_owned(), serde_json::Value::from(*value));
+            }
+        }
+    }
+
+    meta
+}
+
+// A model's top instance is the source folder itself, so it's always written as init files.
+// Its name comes from the project, like the root of any Rojo project, which is why
+// model_top_instance is there to name the project after it.
+fn repr_model_root<'a>(
+    top: &'a Instance,
+    tree: &'a WeakDom,
+    options: &Options,
+) -> Result<Vec<Instruction<'a>>, ConversionError> {
+    let name = top.name.as_str();
+    let mut instructions = vec![Instruction::ModelRoot];
+
+    let meta = match top.class.as_str() {
+        "Script" | "LocalScript" | "ModuleScript" => {
+            instructions.push(Instruction::CreateFile {
+                filename: Cow::Owned(PathBuf::from(format!(
+                    "init{}",
+                    script_extension(top, options)
+                ))),
+                contents: Cow::Borrowed(script_source(tree, top)?),
+            });
+
+            script_meta(top, name, options)
+        }
+
+        "Folder" => meta_file(top, name, options),
+
+        _ => MetaFile {
+            class_name: Some(top.class.to_string()),
+            ..meta_file(top, name, options)
+        },
+    };
+
+    instructions.push(Instruction::CreateFile {
+        filename: Cow::Owned(PathBuf::from("init.meta.json")),
+        contents: meta_contents(&meta)?,
+    });
+
+    Ok(instructions)
+}
+
 fn repr_instance<'a>(
     base: &'a Path,
     child: &'a Instance,
+    name: &str,
+    tree: &'a WeakDom,
     has_scripts: &'a HashMap<Ref, bool>,
-) -> Option<(Vec<Instruction<'a>>, Cow<'a, Path>)> {
+    filtered_out: &HashSet<Ref>,
+    options: &Options,
+) -> Result<Representation<'a>, ConversionError> {
+    if let Some(extension) = native_file_type(child, options) {
+        return repr_native_file(base, child, name, extension, tree, options);
+    }
+
     if has_scripts.get(&child.referent()) != Some(&true) {
-        return None;
+        // Services are still represented as folders so their contents have somewhere to go
+        if !is_service(child.class.as_str(), options) {
+            if !is_creatable(child.class.as_str()) {
+                return Ok(None);
+            }
+
+            return repr_model(
+                base,
+                child,
+                name,
+                tree,
+                filtered_out,
+                options.non_script_instances,
+            );
+        }
+
+        if options.non_script_instances == NonScriptInstances::Drop {
+            return Ok(None);
+        }
     }
 
     match child.class.as_str() {
         "Folder" => {
-            let folder_path = base.join(&child.name);
+            let folder_path = base.join(name);
             let owned: Cow<'a, Path> = Cow::Owned(folder_path);
             let clone = owned.clone();
-            Some((
+            Ok(Some((
                 vec![
                     Instruction::CreateFolder { folder: clone },
                     Instruction::CreateFile {
                         filename: Cow::Owned(owned.join("init.meta.json")),
-                        contents: Cow::Owned(
-                            serde_json::to_string_pretty(&MetaFile {
-                                class_name: None,
-                                // properties: BTreeMap::new(),
-                                ignore_unknown_instances: true,
-                            })
-                            .unwrap()
-                            .as_bytes()
-                            .into(),
-                        ),
+                        contents: meta_contents(&meta_file(child, name, options))?,
                     },
                 ],
-                owned,
-            ))
+                Some(owned),
+            )))
         }
 
         "Script" | "LocalScript" | "ModuleScript" => {
-            let extension = match child.class.as_str() {
-                "Script" => ".server",
-                "LocalScript" => ".client",
-                "ModuleScript" => "",
-                _ => unreachable!(),
-            };
+            let extension = script_extension(child, options);
+            let source = script_source(tree, child)?;
+            let meta = script_meta(child, name, options);
 
-            let source = match child.properties.get(&Ustr::from("Source")).expect("no Source") {
-                Variant::String(value) => value,
-                _ => unreachable!(),
-            }
-            .as_bytes();
+            let represented_children_count = child
+                .children()
+                .iter()
+                .filter(|id| is_represented(**id, has_scripts, filtered_out, options))
+                .count();
+            let total_children_count = child.children().len();
 
-            if child.children().is_empty() {
-                Some((
-                    vec![Instruction::CreateFile {
-                        filename: Cow::Owned(base.join(format!("{}{}.lua", child.name, extension))),
-                        contents: Cow::Borrowed(source),
-                    }],
-                    Cow::Borrowed(base),
-                ))
+            // If there's no represented children, make a named meta file if there's anything to put in it
+            // If there's some represented children, make a folder with a meta file
+            // If there's only represented children, only make a meta file if there's something to put in it
+            if represented_children_count == 0 {
+                let mut instructions = vec![Instruction::CreateFile {
+                    filename: Cow::Owned(base.join(format!("{}{}", name, extension))),
+                    contents: Cow::Borrowed(source),
+                }];
+
+                if total_children_count > 0 || meta.has_contents() {
+                    instructions.push(Instruction::CreateFile {
+                        filename: Cow::Owned(base.join(format!("{}.meta.json", name))),
+                        contents: meta_contents(&meta)?,
+                    });
+                }
+
+                Ok(Some((instructions, Some(Cow::Borrowed(base)))))
             } else {
-                let meta_contents = Cow::Owned(
-                    serde_json::to_string_pretty(&MetaFile {
-                        class_name: None,
-                        // properties: BTreeMap::new(),
-                        ignore_unknown_instances: true,
-                    })
-                    .expect("couldn't serialize meta")
-                    .as_bytes()
-                    .into(),
-                );
-
-                let script_children_count = child
-                    .children()
-                    .iter()
-                    .filter(|id| has_scripts.get(id) == Some(&true))
-                    .count();
-
-                let total_children_count = child.children().len();
-                let folder_path: Cow<'a, Path> = Cow::Owned(base.join(&child.name));
-
-                // If there's no script children, make a named meta file
-                // If there's some script children, make a folder with a meta file
-                // If there's only script children, don't bother with a meta file at all
-                // TODO: Lot of redundant code here
-                match script_children_count {
-                    _ if script_children_count == total_children_count => Some((
-                        vec![
-                            Instruction::CreateFolder {
-                                folder: folder_path.clone(),
-                            },
-                            Instruction::CreateFile {
-                                filename: Cow::Owned(
-                                    folder_path.join(format!("init{}.lua", extension)),
-                                ),
-                                contents: Cow::Borrowed(source),
-                            },
-                        ],
-                        folder_path,
-                    )),
-
-                    0 => Some((
-                        vec![
-                            Instruction::CreateFile {
-                                filename: Cow::Owned(
-                                    base.join(format!("{}{}.lua", child.name, extension)),
-                                ),
-                                contents: Cow::Borrowed(source),
-                            },
-                            Instruction::CreateFile {
-                                filename: Cow::Owned(
-                                    base.join(format!("{}.meta.json", child.name)),
-                                ),
-                                contents: meta_contents,
-                            },
-                        ],
-                        Cow::Borrowed(base),
-                    )),
-
-                    _ => Some((
-                        vec![
-                            Instruction::CreateFolder {
-                                folder: folder_path.clone(),
-                            },
-                            Instruction::CreateFile {
-                                filename: Cow::Owned(
-                                    folder_path.join(format!("init{}.lua", extension)),
-                                ),
-                                contents: Cow::Borrowed(source),
-                            },
-                            Instruction::CreateFile {
-                                filename: Cow::Owned(folder_path.join("init.meta.json")),
-                                contents: meta_contents,
-                            },
-                        ],
-                        folder_path,
-                    )),
+                let folder_path: Cow<'a, Path> = Cow::Owned(base.join(name));
+                let mut instructions = vec![
+                    Instruction::CreateFolder {
+                        folder: folder_path.clone(),
+                    },
+                    Instruction::CreateFile {
+                        filename: Cow::Owned(folder_path.join(format!("init{}", extension))),
+                        contents: Cow::Borrowed(source),
+                    },
+                ];
+
+                if represented_children_count != total_children_count || meta.has_contents() {
+                    instructions.push(Instruction::CreateFile {
+                        filename: Cow::Owned(folder_path.join("init.meta.json")),
+                        contents: meta_contents(&meta)?,
+                    });
                 }
+
+                Ok(Some((instructions, Some(folder_path))))
             }
         }
 
@@ -173,24 +726,30 @@ fn repr_instance<'a>(
             let db = get();
             match db.classes.get(other_class) {
                 Some(reflected) => {
-                    let treat_as_service = RESPECTED_SERVICES.contains(other_class);
-                    // Don't represent services not in respected-services
+                    let treat_as_service = options.respected_services.contains(other_class);
+                    // Don't represent services that aren't respected
                     if reflected.tags.iter().any(|tag| format!("{:?}", tag) == "Service") && !treat_as_service {
-                        return None;
+                        return Ok(None);
                     }
 
                     if treat_as_service {
                         // Don't represent empty services
                         if child.children().is_empty() {
-                            return None;
+                            return Ok(None);
                         }
 
-                        let new_base: Cow<'a, Path> = Cow::Owned(base.join(&child.name));
+                        let new_base: Cow<'a, Path> = Cow::Owned(base.join(name));
                         let mut instructions = Vec::new();
 
-                        if !NON_TREE_SERVICES.contains(other_class) {
-                            instructions
-                                .push(Instruction::add_to_tree(&child, new_base.to_path_buf()));
+                        if !options.non_tree_services.contains(other_class) {
+                            let mut partition =
+                                Instruction::partition(child, new_base.to_path_buf());
+                            partition.properties = service_properties_for(child, options);
+
+                            instructions.push(Instruction::AddToTree {
+                                name: name.to_owned(),
+                                partition,
+                            });
                         }
 
                         if !child.children().is_empty() {
@@ -199,7 +758,7 @@ fn repr_instance<'a>(
                             });
                         }
 
-                        return Some((instructions, new_base));
+                        return Ok(Some((instructions, Some(new_base))));
                     }
                 }
 
@@ -209,138 +768,332 @@ fn repr_instance<'a>(
             }
 
             // If there are scripts, we'll need to make a .meta.json folder
-            let folder_path: Cow<'a, Path> = Cow::Owned(base.join(&child.name));
+            let folder_path: Cow<'a, Path> = Cow::Owned(base.join(name));
             let meta = MetaFile {
                 class_name: Some(child.class.to_string()),
-                // properties: properties.into_iter().collect(),
-                ignore_unknown_instances: true,
+                ..meta_file(child, name, options)
             };
 
-            Some((
+            Ok(Some((
                 vec![
                     Instruction::CreateFolder {
                         folder: folder_path.clone(),
                     },
                     Instruction::CreateFile {
                         filename: Cow::Owned(folder_path.join("init.meta.json")),
-                        contents: Cow::Owned(
-                            serde_json::to_string_pretty(&meta)
-                                .expect("couldn't serialize meta")
-                                .as_bytes()
-                                .into(),
-                        ),
+                        contents: meta_contents(&meta)?,
                     },
                 ],
-                folder_path,
-            ))
+                Some(folder_path),
+            )))
         }
     }
 }
 
 impl<'a, I: InstructionReader + ?Sized> TreeIterator<'a, I> {
-    fn visit_instructions(&mut self, instance: &Instance, has_scripts: &HashMap<Ref, bool>) {
+    fn record_drop(
+        &mut self,
+        child: &Instance,
+        has_scripts: &HashMap<Ref, bool>,
+    ) -> Result<(), ConversionError> {
+        let Some(reason) = drop_reason(child, has_scripts, self.options) else {
+            return Ok(());
+        };
+
+        self.report.dropped.push(Dropped {
+            instance: full_name(self.tree, child),
+            class: child.class.to_string(),
+            reason,
+        });
+
+        match reason {
+            DropReason::NoScripts => {
+                let count = count_by_class(self.tree, child, &mut self.report.dropped_by_class)?;
+                let service = if self.service.is_empty() {
+                    child.name.as_str()
+                } else {
+                    self.service
+                };
+
+                *self
+                    .report
+                    .dropped_by_service
+                    .entry(service.to_owned())
+                    .or_default() += count;
+            }
+
+            DropReason::ServiceNotRespected => {
+                self.report.skipped_services.push(child.name.clone());
+            }
+
+            DropReason::Filtered | DropReason::NotCreatable => {}
+        }
+
+        Ok(())
+    }
+
+    fn record_filtered(&mut self, child: &Instance) {
+        self.report.dropped.push(Dropped {
+            instance: full_name(self.tree, child),
+            class: child.class.to_string(),
+            reason: DropReason::Filtered,
+        });
+    }
+
+    fn visit_instructions(
+        &mut self,
+        instance: &Instance,
+        has_scripts: &HashMap<Ref, bool>,
+    ) -> Result<(), ConversionError> {
         for child_id in instance.children() {
-            let child = self.tree.get_by_ref(*child_id).expect("got fake child id?");
+            let child = get_instance(self.tree, *child_id)?;
+
+            if self.filtered_out.contains(child_id) {
+                self.record_filtered(child);
+                continue;
+            }
 
             let (instructions_to_create_base, path) = if child.class == "StarterPlayer" {
                 // We can't respect StarterPlayer as a service, because then Rojo
                 // tries to delete StarterPlayerScripts and whatnot, which is not valid.
-                let folder_path: Cow<'a, Path> = Cow::Owned(self.path.join(&child.name));
+                let folder_path: Cow<'a, Path> =
+                    Cow::Owned(self.path.join(self.file_names.get(child)));
                 let mut instructions = Vec::new();
 
-                if has_scripts.get(child_id) == Some(&true) {
+                let mut children = BTreeMap::new();
+
+                // Grandchildren are written when they're visited below, which is also where the
+                // ones that are left out get recorded. Each partition points at what's written,
+                // the folder a grandchild becomes or else its file, such as a model file.
+                for grandchild_id in child.children() {
+                    if self.filtered_out.contains(grandchild_id) {
+                        continue;
+                    }
+
+                    let grandchild = get_instance(self.tree, *grandchild_id)?;
+                    let grandchild_name = self.file_names.get(grandchild);
+
+                    let Some((instructions, path)) = repr_instance(
+                        &folder_path,
+                        grandchild,
+                        grandchild_name,
+                        self.tree,
+                        has_scripts,
+                        self.filtered_out,
+                        self.options,
+                    )?
+                    else {
+                        continue;
+                    };
+
+                    let path = match path {
+                        Some(path) if path != folder_path => path.into_owned(),
+                        _ => match instructions.first() {
+                            Some(Instruction::CreateFile { filename, .. }) => filename.to_path_buf(),
+                            _ => continue,
+                        },
+                    };
+
+                    let mut partition = Instruction::partition(grandchild, path);
+                    partition.properties = properties_for(grandchild, self.options);
+                    children.insert(grandchild_name.to_owned(), partition);
+                }
+
+                if !children.is_empty() {
                     instructions.push(Instruction::CreateFolder {
                         folder: folder_path.clone(),
                     });
 
                     instructions.push(Instruction::AddToTree {
-                        name: child.name.clone(),
+                        name: self.file_names.get(child).to_owned(),
                         partition: TreePartition {
                             class_name: child.class.to_string(),
-                            children: child
-                                .children()
-                                .iter()
-                                .filter(|id| has_scripts.get(id) == Some(&true))
-                                .map(|child_id| {
-                                    let child = self.tree.get_by_ref(*child_id).unwrap();
-                                    (
-                                        child.name.clone(),
-                                        Instruction::partition(
-                                            &child,
-                                            folder_path.join(&child.name),
-                                        ),
-                                    )
-                                })
-                                .collect(),
+                            children,
                             ignore_unknown_instances: true,
                             path: None,
+                            properties: service_properties_for(child, self.options),
                         },
                     })
                 }
 
-                (instructions, folder_path)
+                (instructions, Some(folder_path))
             } else {
-                match repr_instance(&self.path, child, has_scripts) {
+                match repr_instance(
+                    self.path,
+                    child,
+                    self.file_names.get(child),
+                    self.tree,
+                    has_scripts,
+                    self.filtered_out,
+                    self.options,
+                )? {
                     Some((instructions_to_create_base, path)) => {
                         (instructions_to_create_base, path)
                     }
-                    None => continue,
+                    None => {
+                        self.record_drop(child, has_scripts)?;
+                        continue;
+                    }
                 }
             };
 
+            count_script(self.report, child);
+            record_unexported(
+                self.report,
+                self.tree,
+                child,
+                &instructions_to_create_base,
+                self.options,
+            );
+
             self.instruction_reader
-                .read_instructions(instructions_to_create_base);
+                .read_instructions(instructions_to_create_base)?;
 
-            TreeIterator {
-                instruction_reader: self.instruction_reader,
-                path: &path,
-                tree: self.tree,
+            if let Some(path) = path {
+                TreeIterator {
+                    file_names: self.file_names,
+                    filtered_out: self.filtered_out,
+                    instruction_reader: self.instruction_reader,
+                    options: self.options,
+                    path: &path,
+                    report: self.report,
+                    service: if self.service.is_empty() {
+                        child.name.as_str()
+                    } else {
+                        self.service
+                    },
+                    tree: self.tree,
+                }
+                .visit_instructions(child, has_scripts)?;
             }
-            .visit_instructions(child, has_scripts);
         }
+
+        Ok(())
     }
 }
 
+// Instances written as native files count as scripts, so the folders they're in get created.
+// Scripts that are filtered out don't count, so they can't bring their ancestors along.
 fn check_has_scripts(
     tree: &WeakDom,
     instance: &Instance,
     has_scripts: &mut HashMap<Ref, bool>,
-) -> bool {
+    filtered_out: &HashSet<Ref>,
+    options: &Options,
+) -> Result<bool, ConversionError> {
     let mut children_have_scripts = false;
 
     for child_id in instance.children() {
-        let result = check_has_scripts(
-            tree,
-            tree.get_by_ref(*child_id).expect("fake child id?"),
-            has_scripts,
-        );
+        if filtered_out.contains(child_id) {
+            continue;
+        }
+
+        let child = get_instance(tree, *child_id)?;
+        let result = check_has_scripts(tree, child, has_scripts, filtered_out, options)?;
 
         children_have_scripts = children_have_scripts || result;
     }
 
     let result = match instance.class.as_str() {
         "Script" | "LocalScript" | "ModuleScript" => true,
-        _ => children_have_scripts,
+        _ => children_have_scripts || native_file_type(instance, options).is_some(),
     };
 
     has_scripts.insert(instance.referent(), result);
-    result
+    Ok(result)
 }
 
-pub fn process_instructions(tree: &WeakDom, instruction_reader: &mut dyn InstructionReader) {
-    let root = tree.root_ref();
-    let root_instance = tree.get_by_ref(root).expect("fake root id?");
+// The top instances of a model that are left after Options::include and Options::exclude
+fn top_instances<'a>(
+    tree: &'a WeakDom,
+    filtered_out: &HashSet<Ref>,
+) -> Result<Vec<&'a Instance>, ConversionError> {
+    let mut top_instances = Vec::new();
+    for child_id in get_instance(tree, tree.root_ref())?.children() {
+        if !filtered_out.contains(child_id) {
+            top_instances.push(get_instance(tree, *child_id)?);
+        }
+    }
+
+    Ok(top_instances)
+}
+
+// The instance a model project is rooted at, if it has only one top instance.
+// Rojo names a project's root after the project, so the project should share its name.
+pub fn model_top_instance<'a>(
+    tree: &'a WeakDom,
+    options: &Options,
+) -> Result<Option<&'a Instance>, ConversionError> {
+    match top_instances(tree, &filtered_out(tree, options)?)?.as_slice() {
+        [top] => Ok(Some(*top)),
+        _ => Ok(None),
+    }
+}
+
+pub fn process_instructions(
+    tree: &WeakDom,
+    instruction_reader: &mut dyn InstructionReader,
+) -> Result<Report, ConversionError> {
+    process_instructions_with_options(tree, instruction_reader, &Options::default())
+}
+
+pub fn process_instructions_with_options(
+    tree: &WeakDom,
+    instruction_reader: &mut dyn InstructionReader,
+    options: &Options,
+) -> Result<Report, ConversionError> {
+    let root_instance = get_instance(tree, tree.root_ref())?;
     let path = PathBuf::new();
+    let mut report = Report::default();
+
+    let filtered_out = filtered_out(tree, options)?;
 
     let mut has_scripts = HashMap::new();
-    check_has_scripts(tree, root_instance, &mut has_scripts);
+    check_has_scripts(tree, root_instance, &mut has_scripts, &filtered_out, options)?;
+
+    // Models with one top instance are rooted at it, otherwise the root holds them all
+    let top_instances = top_instances(tree, &filtered_out)?;
+    let model_top = match (options.project_kind, top_instances.as_slice()) {
+        (ProjectKind::Model, [top]) => Some(*top),
+        _ => None,
+    };
+    let visited = model_top.unwrap_or(root_instance);
+
+    // Names are resolved from the same instance the tree is visited from, so that
+    // everything written under it has a safe name
+    let file_names = FileNames::resolve(tree, visited, &has_scripts, &filtered_out, options)?;
+
+    let service = match (options.project_kind, model_top) {
+        (ProjectKind::Place, _) => "",
+
+        (ProjectKind::Model, Some(top)) => {
+            let instructions = repr_model_root(top, tree, options)?;
+            count_script(&mut report, top);
+            record_unexported(&mut report, tree, top, &instructions, options);
+            instruction_reader.read_instructions(instructions)?;
+            top.name.as_str()
+        }
+
+        (ProjectKind::Model, None) => {
+            instruction_reader.read_instruction(Instruction::ModelRoot)?;
+            ""
+        }
+    };
 
     TreeIterator {
+        file_names: &file_names,
+        filtered_out: &filtered_out,
         instruction_reader,
+        options,
         path: &path,
+        report: &mut report,
+        service,
         tree,
     }
-    .visit_instructions(&root_instance, &has_scripts);
+    .visit_instructions(visited, &has_scripts)?;
+
+    instruction_reader.finish_instructions()?;
 
-    instruction_reader.finish_instructions();
+    report.renamed = file_names.renamed;
+    Ok(report)
 }
\ No newline at end of file
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+// One entry of a LocalizationTable's Contents, which Roblox stores as a JSON array
+#[derive(Default, Deserialize, Serialize)]
+struct Entry {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    context: String,
+    #[serde(default)]
+    examples: String,
+    #[serde(default)]
+    values: BTreeMap<String, String>,
+}
+
+// Splits CSV into rows of fields, handling quoted fields with commas, quotes and newlines in them
+fn parse_rows(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = csv.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if quoted && characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            other => field.push(other),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn push_row<'a>(csv: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+    let fields: Vec<_> = fields.into_iter().map(escape).collect();
+    csv.push_str(&fields.join(","));
+    csv.push('\n');
+}
+
+// Turns the Contents of a LocalizationTable into the CSV format Rojo reads,
+// or None if Contents isn't valid
+pub(crate) fn to_csv(contents: &str) -> Option<String> {
+    let entries: Vec<Entry> = if contents.trim().is_empty() {
+        Vec::new()
+    } else {
+        serde_json::from_str(contents).ok()?
+    };
+
+    let locales: BTreeSet<&str> = entries
+        .iter()
+        .flat_map(|entry| entry.values.keys().map(String::as_str))
+        .collect();
+
+    let mut csv = String::new();
+    push_row(
+        &mut csv,
+        ["Key", "Source", "Context", "Example"]
+            .into_iter()
+            .chain(locales.iter().copied()),
+    );
+
+    for entry in &entries {
+        push_row(
+            &mut csv,
+            [
+                entry.key.as_str(),
+                entry.source.as_str(),
+                entry.context.as_str(),
+                entry.examples.as_str(),
+            ]
+            .into_iter()
+            .chain(
+                locales
+                    .iter()
+                    .map(|locale| entry.values.get(*locale).map_or("", String::as_str)),
+            ),
+        );
+    }
+
+    Some(csv)
+}
+
+// Turns a CSV file written by to_csv (or by hand, in the format Rojo reads) back into
+// the Contents of a LocalizationTable, or None if it has no header
+pub(crate) fn from_csv(csv: &str) -> Option<String> {
+    let mut rows = parse_rows(csv).into_iter();
+    let header = rows.next()?;
+
+    let entries: Vec<Entry> = rows
+        .map(|row| {
+            let mut entry = Entry::default();
+
+            for (column, value) in header.iter().zip(row) {
+                match column.as_str() {
+                    "Key" => entry.key = value,
+                    "Source" => entry.source = value,
+                    "Context" => entry.context = value,
+                    "Example" => entry.examples = value,
+                    locale if !value.is_empty() => {
+                        entry.values.insert(locale.to_owned(), value);
+                    }
+                    _ => {}
+                }
+            }
+
+            entry
+        })
+        .collect();
+
+    serde_json::to_string(&entries).ok()
+}
@@ -0,0 +1,219 @@
+use crate::{
+    error::ConversionError, file_extensions, full_name, get_instance, is_represented, is_service,
+    report::{Rename, RenameReason},
+    structures::*,
+};
+use rbx_dom_weak::{types::Ref, Instance, WeakDom};
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+};
+use unicode_normalization::UnicodeNormalization;
+
+const ILLEGAL_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// Turns an instance name into one that is safe to use as a file name on every platform
+// and that Rojo won't read as anything other than a plain instance
+fn sanitize(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|character| {
+            if character.is_control() || ILLEGAL_CHARACTERS.contains(&character) {
+                '_'
+            } else {
+                character
+            }
+        })
+        .collect();
+
+    // Windows strips trailing dots and spaces, which also takes care of "." and ".."
+    if sanitized.is_empty() || sanitized.ends_with(['.', ' ']) {
+        sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+        sanitized.push('_');
+    }
+
+    // Rojo reads these as part of the file type, so a ModuleScript named Foo.server
+    // would be written as Foo.server.lua and read back as a Script named Foo
+    if [".server", ".client", ".meta"]
+        .iter()
+        .any(|suffix| sanitized.ends_with(suffix))
+    {
+        sanitized.push('_');
+    }
+
+    // Reserved device names are reserved no matter what extension follows them,
+    // and a child named init would be read as its parent's script
+    let stem_length = sanitized.find('.').unwrap_or(sanitized.len());
+    let stem = &sanitized[..stem_length];
+    if stem.eq_ignore_ascii_case("init")
+        || RESERVED_NAMES
+            .iter()
+            .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        sanitized.insert(stem_length, '_');
+    }
+
+    sanitized
+}
+
+// Names with the same key end up as the same file on the given platform
+fn collision_key(name: &str, platform: Platform) -> String {
+    match platform {
+        Platform::Linux => name.to_owned(),
+        Platform::Windows => name.to_lowercase(),
+        Platform::Any | Platform::MacOs => name.nfc().collect::<String>().to_lowercase(),
+    }
+}
+
+// The keys of everything an instance with this name writes beside its siblings. Files can have
+// a meta file next to them, which a Script and a ModuleScript both named Foo would share.
+fn collision_keys(name: &str, extensions: &[String], platform: Platform) -> Vec<String> {
+    if extensions.is_empty() {
+        return vec![collision_key(name, platform)];
+    }
+
+    extensions
+        .iter()
+        .map(|extension| format!("{}{}", name, extension))
+        .chain(iter::once(format!("{}.meta.json", name)))
+        .map(|file_name| collision_key(&file_name, platform))
+        .collect()
+}
+
+// The names instances are given on disk, decided before anything is written
+// so that siblings sharing a name can't overwrite each other.
+#[derive(Debug, Default)]
+pub(crate) struct FileNames {
+    names: HashMap<Ref, String>,
+    pub renamed: Vec<Rename>,
+}
+
+impl FileNames {
+    pub fn get<'a>(&'a self, instance: &'a Instance) -> &'a str {
+        self.names
+            .get(&instance.referent())
+            .map_or(instance.name.as_str(), String::as_str)
+    }
+
+    // Names the descendants of visited, which is the root, or a model's top instance
+    pub fn resolve(
+        tree: &WeakDom,
+        visited: &Instance,
+        has_scripts: &HashMap<Ref, bool>,
+        filtered_out: &HashSet<Ref>,
+        options: &Options,
+    ) -> Result<Self, ConversionError> {
+        let mut file_names = FileNames::default();
+        let mut collisions = Vec::new();
+
+        file_names.visit(
+            tree,
+            visited,
+            has_scripts,
+            filtered_out,
+            options,
+            &mut collisions,
+        )?;
+
+        if collisions.is_empty() {
+            Ok(file_names)
+        } else {
+            Err(ConversionError::NameCollisions(collisions))
+        }
+    }
+
+    fn visit(
+        &mut self,
+        tree: &WeakDom,
+        instance: &Instance,
+        has_scripts: &HashMap<Ref, bool>,
+        filtered_out: &HashSet<Ref>,
+        options: &Options,
+        collisions: &mut Vec<String>,
+    ) -> Result<(), ConversionError> {
+        let mut children = Vec::new();
+        for child_id in instance.children() {
+            let child = get_instance(tree, *child_id)?;
+            if is_represented(child.referent(), has_scripts, filtered_out, options) {
+                children.push(child);
+            }
+        }
+
+        let safe_names: Vec<String> = children.iter().map(|child| sanitize(&child.name)).collect();
+        let extensions: Vec<Vec<String>> = children
+            .iter()
+            .map(|child| file_extensions(child, has_scripts, filtered_out, options))
+            .collect();
+
+        // Keys come from the files that are actually written, so that a Script named Foo and
+        // a Folder named Foo can sit side by side, but nothing can overwrite anything else
+        let keys = |name: &str, extensions: &[String]| {
+            collision_keys(name, extensions, options.platform)
+        };
+        let taken: HashSet<String> = safe_names
+            .iter()
+            .zip(&extensions)
+            .flat_map(|(name, extensions)| keys(name, extensions))
+            .collect();
+        let mut used = HashSet::new();
+
+        for ((child, safe_name), extensions) in children.iter().zip(&safe_names).zip(&extensions) {
+            let file_name = if keys(safe_name, extensions).iter().all(|key| !used.contains(key)) {
+                safe_name.clone()
+            } else {
+                match options.collisions {
+                    CollisionStrategy::Error => {
+                        collisions.push(full_name(tree, child));
+                        continue;
+                    }
+
+                    CollisionStrategy::Rename => {
+                        let mut number = 2;
+                        loop {
+                            let name = format!("{} ({})", safe_name, number);
+                            if keys(&name, extensions)
+                                .iter()
+                                .all(|key| !taken.contains(key) && !used.contains(key))
+                            {
+                                break name;
+                            }
+
+                            number += 1;
+                        }
+                    }
+                }
+            };
+
+            used.extend(keys(&file_name, extensions));
+            if file_name != child.name {
+                self.renamed.push(Rename {
+                    instance: full_name(tree, child),
+                    file_name: file_name.clone(),
+                    reason: if file_name == *safe_name {
+                        RenameReason::UnsafeName
+                    } else {
+                        RenameReason::Collision
+                    },
+                });
+                self.names.insert(child.referent(), file_name);
+            }
+        }
+
+        for child in children {
+            // Model files hold their whole subtree, so only folders need their children named
+            if has_scripts.get(&child.referent()) == Some(&true)
+                || (options.non_script_instances != NonScriptInstances::Drop
+                    && is_service(child.class.as_str(), options))
+            {
+                self.visit(tree, child, has_scripts, filtered_out, options, collisions)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use rbx_dom_weak::{types::Variant, Instance, Ustr};
+use rbx_reflection_database::get;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Encodes a property value the way Rojo reads it from .meta.json and .model.json files.
+// Strings and bools are unambiguous, so they use the implicit syntax, everything else
+// uses the explicit syntax, which is what rbx_types serializes to.
+// Returns None if Rojo has no way of representing the value.
+pub(crate) fn encode_value(value: &Variant) -> Option<Value> {
+    match value {
+        Variant::String(value) => Some(Value::from(value.as_str())),
+        Variant::Bool(value) => Some(Value::from(*value)),
+
+        Variant::Attributes(_)
+        | Variant::Ref(_)
+        | Variant::SharedString(_)
+        | Variant::Tags(_)
+        | Variant::UniqueId(_) => None,
+
+        other => serde_json::to_value(other).ok(),
+    }
+}
+
+// CollectionService tags, as the list Rojo reads from the Tags property.
+pub(crate) fn encode_tags(instance: &Instance) -> Option<Value> {
+    match instance.properties.get(&Ustr::from("Tags")) {
+        Some(Variant::Tags(tags)) if tags.iter().next().is_some() => {
+            Some(tags.iter().map(Value::from).collect())
+        }
+        _ => None,
+    }
+}
+
+// Attributes, as the map Rojo reads from the attributes field of meta and model files.
+// Returns None if any of them can't be encoded.
+pub(crate) fn encode_attributes(instance: &Instance) -> Option<BTreeMap<String, Value>> {
+    let mut attributes = BTreeMap::new();
+
+    if let Some(Variant::Attributes(values)) = instance.properties.get(&Ustr::from("Attributes")) {
+        for (key, value) in values.iter() {
+            attributes.insert(key.clone(), encode_value(value)?);
+        }
+    }
+
+    Some(attributes)
+}
+
+// Every scriptable property on the instance that isn't the class default.
+fn changed_properties(instance: &Instance) -> Vec<(&Ustr, &Variant)> {
+    let db = get();
+    let mut properties = Vec::new();
+
+    for (key, value) in &instance.properties {
+        // Source is what gets written into the script file itself
+        if key.as_str() == "Source" {
+            continue;
+        }
+
+        let mut descriptor = None;
+        let mut default = None;
+        let mut class_name = Some(instance.class.as_str());
+
+        while let Some(name) = class_name {
+            let Some(class) = db.classes.get(name) else {
+                break;
+            };
+
+            descriptor = descriptor.or_else(|| class.properties.get(key.as_str()));
+            default = default.or_else(|| class.default_properties.get(key.as_str()));
+            class_name = class.superclass.as_deref();
+        }
+
+        let Some(descriptor) = descriptor else {
+            continue;
+        };
+
+        // The database's types come from a different rbx_types than rbx_dom_weak's,
+        // so compare them by what they look like rather than by type
+        if format!("{:?}", descriptor.scriptability) != "ReadWrite"
+            || !format!("{:?}", descriptor.kind).starts_with("Canonical")
+        {
+            continue;
+        }
+
+        if let Some(default) = default
+            && serde_json::to_value(default).ok() == serde_json::to_value(value).ok()
+        {
+            continue;
+        }
+
+        properties.push((key, value));
+    }
+
+    properties
+}
+
+// Every scriptable property on the instance that isn't the class default and can be encoded.
+pub(crate) fn non_default_properties(instance: &Instance) -> BTreeMap<String, Value> {
+    changed_properties(instance)
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), encode_value(value)?)))
+        .collect()
+}
+
+// The properties non_default_properties leaves out because Rojo can't represent them in a meta
+// file or a project, such as a Model's PrimaryPart. Tags and attributes have their own fields,
+// UniqueIds are given out again when the place loads, and empty Refs are what Rojo gives
+// instances anyway, so none of those are lost.
+pub(crate) fn unexported_properties(instance: &Instance) -> Vec<String> {
+    changed_properties(instance)
+        .into_iter()
+        .filter(|(_, value)| match value {
+            Variant::Tags(_) | Variant::Attributes(_) | Variant::UniqueId(_) => false,
+            Variant::Ref(referent) => !referent.is_none(),
+            value => encode_value(value).is_none(),
+        })
+        .map(|(key, _)| key.to_string())
+        .collect()
+}
@@ -0,0 +1,411 @@
+use crate::{error::ConversionError, localization, structures::*};
+use rbx_dom_weak::{
+    types::{Attributes, Ref, Tags, Variant},
+    InstanceBuilder, Ustr, WeakDom,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+const INIT_SCRIPTS: &[(&str, &str)] = &[
+    ("init.server.lua", "Script"),
+    ("init.server.luau", "Script"),
+    ("init.client.lua", "LocalScript"),
+    ("init.client.luau", "LocalScript"),
+    ("init.lua", "ModuleScript"),
+    ("init.luau", "ModuleScript"),
+];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProjectTree {
+    Place(TreePartition),
+    // Model projects are only a $path, and the folder says what class the root is
+    Model {
+        #[serde(rename = "$path")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Deserialize)]
+struct ProjectFile {
+    name: String,
+    tree: ProjectTree,
+}
+
+enum FileKind {
+    Csv,
+    Meta,
+    ModelJson,
+    Rbxm,
+    Rbxmx,
+    Script(&'static str),
+    Text,
+}
+
+// Longer suffixes come first, so that Name.server.lua isn't read as a ModuleScript
+// named Name.server
+fn classify(file_name: &str) -> Option<(&str, FileKind)> {
+    let kinds = [
+        (".server.lua", FileKind::Script("Script")),
+        (".server.luau", FileKind::Script("Script")),
+        (".client.lua", FileKind::Script("LocalScript")),
+        (".client.luau", FileKind::Script("LocalScript")),
+        (".meta.json", FileKind::Meta),
+        (".model.json", FileKind::ModelJson),
+        (".lua", FileKind::Script("ModuleScript")),
+        (".luau", FileKind::Script("ModuleScript")),
+        (".rbxmx", FileKind::Rbxmx),
+        (".rbxm", FileKind::Rbxm),
+        (".txt", FileKind::Text),
+        (".csv", FileKind::Csv),
+    ];
+
+    kinds
+        .into_iter()
+        .find_map(|(suffix, kind)| Some((file_name.strip_suffix(suffix)?, kind)))
+}
+
+fn io_error<'a>(
+    doing_what: &'static str,
+    path: &'a Path,
+) -> impl FnOnce(io::Error) -> ConversionError + 'a {
+    move |error| ConversionError::IoError(doing_what, path.to_path_buf(), error)
+}
+
+fn read_string(path: &Path) -> Result<String, ConversionError> {
+    fs::read_to_string(path).map_err(io_error("read file", path))
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, ConversionError> {
+    serde_json::from_str(&read_string(path)?)
+        .map_err(|error| ConversionError::InvalidJson(path.to_path_buf(), error))
+}
+
+// Reads values in Rojo's implicit and explicit property syntax, which is what encode_value writes
+fn decode_value(path: &Path, key: &str, value: Value) -> Result<Variant, ConversionError> {
+    let invalid = || ConversionError::InvalidProperty(path.to_path_buf(), key.to_owned());
+
+    match value {
+        Value::String(value) => Ok(Variant::String(value)),
+        Value::Bool(value) => Ok(Variant::Bool(value)),
+        // Rojo reads plain numbers as doubles too
+        Value::Number(number) => number.as_f64().map(Variant::Float64).ok_or_else(invalid),
+        Value::Array(tags) if key == "Tags" => tags
+            .into_iter()
+            .map(|tag| match tag {
+                Value::String(tag) => Ok(tag),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|tags| Variant::Tags(Tags::from(tags))),
+        other => serde_json::from_value(other).map_err(|_| invalid()),
+    }
+}
+
+fn decode_attributes(
+    path: &Path,
+    values: BTreeMap<String, Value>,
+) -> Result<Variant, ConversionError> {
+    let mut attributes = Attributes::new();
+    for (key, value) in values {
+        let value = decode_value(path, &key, value)?;
+        attributes.insert(key, value);
+    }
+
+    Ok(Variant::Attributes(attributes))
+}
+
+fn apply_meta(
+    dom: &mut WeakDom,
+    referent: Ref,
+    path: &Path,
+    meta: MetaFile,
+) -> Result<(), ConversionError> {
+    let mut properties = Vec::new();
+    for (key, value) in meta.properties {
+        let value = decode_value(path, &key, value)?;
+        properties.push((key, value));
+    }
+
+    if !meta.attributes.is_empty() {
+        properties.push(("Attributes".to_owned(), decode_attributes(path, meta.attributes)?));
+    }
+
+    let instance = dom
+        .get_by_ref_mut(referent)
+        .ok_or(ConversionError::MissingInstance(referent))?;
+
+    if let Some(name) = meta.name {
+        instance.name = name;
+    }
+
+    for (key, value) in properties {
+        instance.properties.insert(Ustr::from(key.as_str()), value);
+    }
+
+    Ok(())
+}
+
+fn insert_json_model(
+    dom: &mut WeakDom,
+    parent: Ref,
+    path: &Path,
+    name: &str,
+    model: JsonModel,
+) -> Result<Ref, ConversionError> {
+    let mut builder = InstanceBuilder::new(model.class_name.as_str())
+        .with_name(model.name.as_deref().unwrap_or(name));
+
+    for (key, value) in model.properties {
+        builder = builder.with_property(key.as_str(), decode_value(path, &key, value)?);
+    }
+
+    if !model.attributes.is_empty() {
+        builder = builder.with_property("Attributes", decode_attributes(path, model.attributes)?);
+    }
+
+    let referent = dom.insert(parent, builder);
+    for child in model.children {
+        insert_json_model(dom, referent, path, "", child)?;
+    }
+
+    Ok(referent)
+}
+
+// Moves the contents of a model file into the place, named after the file like Rojo does
+fn insert_model_file(
+    dom: &mut WeakDom,
+    parent: Ref,
+    path: &Path,
+    name: &str,
+    binary: bool,
+) -> Result<Option<Ref>, ConversionError> {
+    let file = BufReader::new(File::open(path).map_err(io_error("read file", path))?);
+    let mut model = if binary {
+        rbx_binary::from_reader(file)
+            .map_err(|error| ConversionError::BinaryDecodeError(path.to_path_buf(), error))?
+    } else {
+        rbx_xml::from_reader_default(file)
+            .map_err(|error| ConversionError::XmlDecodeError(path.to_path_buf(), error))?
+    };
+
+    let roots = model.root().children().to_vec();
+    for referent in &roots {
+        model.transfer(*referent, dom, parent);
+    }
+
+    let first = roots.first().copied();
+    if let Some(instance) = first.and_then(|referent| dom.get_by_ref_mut(referent)) {
+        instance.name = name.to_owned();
+    }
+
+    Ok(first)
+}
+
+fn read_children(dom: &mut WeakDom, parent: Ref, folder: &Path) -> Result<(), ConversionError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(folder).map_err(io_error("read folder", folder))? {
+        paths.push(entry.map_err(io_error("read folder", folder))?.path());
+    }
+    paths.sort();
+
+    let mut metas = HashMap::new();
+    for path in &paths {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some((stem, FileKind::Meta)) =
+            classify(&file_name).filter(|(stem, _)| *stem != "init")
+        {
+            metas.insert(stem.to_owned(), read_json::<MetaFile>(path)?);
+        }
+    }
+
+    for path in &paths {
+        if path.is_dir() {
+            read_folder(dom, parent, path)?;
+            continue;
+        }
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        // Files Rojo doesn't know about are left alone, like Rojo does
+        let Some((stem, kind)) = classify(&file_name) else {
+            continue;
+        };
+
+        // These belong to the folder itself
+        if stem == "init" {
+            continue;
+        }
+
+        let referent = match kind {
+            FileKind::Meta => continue,
+
+            FileKind::Script(class) => dom.insert(
+                parent,
+                InstanceBuilder::new(class)
+                    .with_name(stem)
+                    .with_property("Source", Variant::String(read_string(path)?)),
+            ),
+
+            FileKind::Text => dom.insert(
+                parent,
+                InstanceBuilder::new("StringValue")
+                    .with_name(stem)
+                    .with_property("Value", Variant::String(read_string(path)?)),
+            ),
+
+            FileKind::Csv => {
+                let contents = localization::from_csv(&read_string(path)?).ok_or_else(|| {
+                    ConversionError::InvalidLocalizationTable(path.display().to_string())
+                })?;
+
+                dom.insert(
+                    parent,
+                    InstanceBuilder::new("LocalizationTable")
+                        .with_name(stem)
+                        .with_property("Contents", Variant::String(contents)),
+                )
+            }
+
+            FileKind::ModelJson => {
+                insert_json_model(dom, parent, path, stem, read_json::<JsonModel>(path)?)?
+            }
+
+            FileKind::Rbxm | FileKind::Rbxmx => {
+                let binary = matches!(kind, FileKind::Rbxm);
+                match insert_model_file(dom, parent, path, stem, binary)? {
+                    Some(referent) => referent,
+                    None => continue,
+                }
+            }
+        };
+
+        if let Some(meta) = metas.remove(stem) {
+            apply_meta(dom, referent, path, meta)?;
+        }
+    }
+
+    Ok(())
+}
+
+// A folder is a Folder, or the script in its init file, or the class in its init.meta.json
+fn read_folder(dom: &mut WeakDom, parent: Ref, folder: &Path) -> Result<Ref, ConversionError> {
+    let name = folder.file_name().unwrap_or_default().to_string_lossy();
+
+    let meta_path = folder.join("init.meta.json");
+    let meta = if meta_path.is_file() {
+        Some(read_json::<MetaFile>(&meta_path)?)
+    } else {
+        None
+    };
+
+    let script = INIT_SCRIPTS
+        .iter()
+        .map(|(file_name, class)| (folder.join(file_name), *class))
+        .find(|(path, _)| path.is_file());
+
+    let builder = match script {
+        Some((path, class)) => InstanceBuilder::new(class)
+            .with_property("Source", Variant::String(read_string(&path)?)),
+        None => InstanceBuilder::new(
+            meta.as_ref()
+                .and_then(|meta| meta.class_name.as_deref())
+                .unwrap_or("Folder"),
+        ),
+    };
+
+    let referent = dom.insert(parent, builder.with_name(name.as_ref()));
+    if let Some(meta) = meta {
+        apply_meta(dom, referent, &meta_path, meta)?;
+    }
+
+    read_children(dom, referent, folder)?;
+    Ok(referent)
+}
+
+fn read_partition(
+    dom: &mut WeakDom,
+    parent: Ref,
+    name: &str,
+    partition: TreePartition,
+    project_folder: &Path,
+) -> Result<(), ConversionError> {
+    let mut builder = InstanceBuilder::new(partition.class_name.as_str()).with_name(name);
+    for (key, value) in partition.properties {
+        let value = decode_value(&project_folder.join("default.project.json"), &key, value)?;
+        builder = builder.with_property(key.as_str(), value);
+    }
+
+    let referent = dom.insert(parent, builder);
+
+    if let Some(path) = &partition.path {
+        let folder = project_folder.join(path);
+        if folder.is_dir() {
+            read_children(dom, referent, &folder)?;
+        }
+    }
+
+    for (child_name, child) in partition.children {
+        read_partition(dom, referent, &child_name, child, project_folder)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_project(
+    project_folder: &Path,
+) -> Result<(WeakDom, ProjectKind), ConversionError> {
+    let project: ProjectFile = read_json(&project_folder.join("default.project.json"))?;
+
+    match project.tree {
+        ProjectTree::Place(tree) => {
+            let mut dom = WeakDom::new(InstanceBuilder::new(tree.class_name.as_str()));
+            let root = dom.root_ref();
+
+            for (name, partition) in tree.children {
+                read_partition(&mut dom, root, &name, partition, project_folder)?;
+            }
+
+            Ok((dom, ProjectKind::Place))
+        }
+
+        ProjectTree::Model { path } => {
+            let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+            let root = dom.root_ref();
+            let referent = read_folder(&mut dom, root, &project_folder.join(path))?;
+
+            // Like Rojo, the root is named after the project rather than its folder
+            if let Some(instance) = dom.get_by_ref_mut(referent) {
+                instance.name = project.name;
+            }
+
+            Ok((dom, ProjectKind::Model))
+        }
+    }
+}
+
+// Builds what a project made by the converter describes, from its default.project.json
+// and the files it points to, the same way Rojo would build it. Places come back as a
+// DataModel, and models as the model's top instance under an empty root.
+pub fn rebuild(project_folder: &Path) -> Result<WeakDom, ConversionError> {
+    Ok(read_project(project_folder)?.0)
+}
+
+// Writes what was rebuilt as a binary file if binary is set, or an XML file otherwise
+pub fn write_place<W: Write>(dom: &WeakDom, output: W, binary: bool) -> Result<(), ConversionError> {
+    let children = dom.root().children();
+
+    if binary {
+        rbx_binary::to_writer(output, dom, children)
+            .map_err(|error| ConversionError::BinaryEncodeError("the project".to_owned(), error))
+    } else {
+        rbx_xml::to_writer_default(output, dom, children)
+            .map_err(|error| ConversionError::XmlEncodeError("the project".to_owned(), error))
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub scripts: usize,
+    pub scripts_by_class: BTreeMap<String, usize>,
+    // The total size of every script's Source
+    pub source_bytes: usize,
+    // Instances left out because nothing under them is a script,
+    // counted by class and by the service they were in
+    pub dropped_by_class: BTreeMap<String, usize>,
+    pub dropped_by_service: BTreeMap<String, usize>,
+    // Services left out because they aren't in Options::respected_services
+    pub skipped_services: Vec<String>,
+    // Every instance that was left out along with everything under it
+    pub dropped: Vec<Dropped>,
+    // Instances that had to be given a different name on disk
+    pub renamed: Vec<Rename>,
+    // Properties that couldn't be written to a meta file or the project, such as a Model's
+    // PrimaryPart, only looked for when properties are exported
+    pub unexported_properties: Vec<UnexportedProperty>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum DropReason {
+    // Nothing under it is a script, and non-script instances are being dropped
+    NoScripts,
+    // It's a service that isn't in Options::respected_services
+    ServiceNotRespected,
+    // It didn't match Options::include, or it matched Options::exclude
+    Filtered,
+    // It has no scripts, and Rojo can't create its class from a model file, like Terrain
+    NotCreatable,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Dropped {
+    // The full path of the instance, such as Workspace.Map.Tree42
+    pub instance: String,
+    pub class: String,
+    pub reason: DropReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum RenameReason {
+    // A sibling already had the same file name
+    Collision,
+    // The instance's name isn't a safe file name
+    UnsafeName,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Rename {
+    // The full path of the instance, such as ReplicatedStorage.Util
+    pub instance: String,
+    pub file_name: String,
+    pub reason: RenameReason,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UnexportedProperty {
+    // The full path of the instance, such as Workspace.Map
+    pub instance: String,
+    pub property: String,
+}
+
+impl fmt::Display for DropReason {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DropReason::NoScripts => write!(formatter, "it has no scripts"),
+            DropReason::ServiceNotRespected => {
+                write!(formatter, "it's a service that isn't respected")
+            }
+            DropReason::Filtered => write!(formatter, "it was filtered out"),
+            DropReason::NotCreatable => write!(formatter, "Rojo can't create its class"),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            formatter,
+            "Converted {} scripts ({} bytes of source)",
+            self.scripts, self.source_bytes,
+        )?;
+
+        for (class, count) in &self.scripts_by_class {
+            writeln!(formatter, "  {}: {}", class, count)?;
+        }
+
+        let dropped: usize = self.dropped_by_class.values().sum();
+        if dropped > 0 {
+            writeln!(formatter, "Left out {} instances without scripts", dropped)?;
+            for (service, count) in &self.dropped_by_service {
+                writeln!(formatter, "  in {}: {}", service, count)?;
+            }
+        }
+
+        if !self.skipped_services.is_empty() {
+            writeln!(
+                formatter,
+                "Left out services that aren't respected: {}",
+                self.skipped_services.join(", "),
+            )?;
+        }
+
+        let filtered = self
+            .dropped
+            .iter()
+            .filter(|dropped| dropped.reason == DropReason::Filtered)
+            .count();
+
+        if filtered > 0 {
+            writeln!(formatter, "Filtered out {} instances", filtered)?;
+        }
+
+        let collisions = self
+            .renamed
+            .iter()
+            .filter(|rename| rename.reason == RenameReason::Collision)
+            .count();
+
+        if !self.unexported_properties.is_empty() {
+            writeln!(
+                formatter,
+                "Couldn't export {} properties",
+                self.unexported_properties.len(),
+            )?;
+        }
+
+        write!(
+            formatter,
+            "Renamed {} instances on disk ({} name collisions)",
+            self.renamed.len(),
+            collisions,
+        )
+    }
+}
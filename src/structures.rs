@@ -1,8 +1,9 @@
+use crate::error::ConversionError;
 use rbx_dom_weak::Instance;
 use serde::{Deserialize, Serialize, Serializer};
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
 };
 
@@ -31,15 +32,17 @@ pub struct TreePartition {
     pub children: BTreeMap<String, TreePartition>,
 
     #[serde(rename = "$ignoreUnknownInstances")]
+    #[serde(default)]
     pub ignore_unknown_instances: bool,
 
     #[serde(rename = "$path")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(serialize_with = "replace_backslashes")]
     pub path: Option<PathBuf>,
-    // #[serde(rename = "$properties")]
-    // #[serde(skip_serializing_if = "BTreeMap::is_empty")]
-    // pub properties: BTreeMap<String, RbxValue>,
+
+    #[serde(rename = "$properties")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -48,13 +51,174 @@ pub(crate) struct MetaFile {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub class_name: Option<String>,
 
-    // #[serde(rename = "properties")]
-    // #[serde(skip_serializing_if = "BTreeMap::is_empty")]
-    // pub properties: BTreeMap<String, RbxValue>,
+    #[serde(rename = "name")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(rename = "properties")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+
+    #[serde(rename = "attributes")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+
     #[serde(rename = "ignoreUnknownInstances")]
+    #[serde(default)]
     pub ignore_unknown_instances: bool,
 }
 
+impl MetaFile {
+    // Whether the meta file says anything besides ignoreUnknownInstances
+    pub fn has_contents(&self) -> bool {
+        self.class_name.is_some()
+            || self.name.is_some()
+            || !self.properties.is_empty()
+            || !self.attributes.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub(crate) struct JsonModel {
+    #[serde(rename = "name")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(rename = "className")]
+    pub class_name: String,
+
+    #[serde(rename = "properties")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+
+    #[serde(rename = "attributes")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+
+    #[serde(rename = "children")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<JsonModel>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum NonScriptInstances {
+    // Subtrees without scripts are left out of the project
+    #[default]
+    Drop,
+    // Subtrees without scripts are serialized into Name.rbxmx
+    Rbxmx,
+    // Subtrees without scripts are serialized into Name.rbxm
+    Rbxm,
+    // Subtrees without scripts are written as Name.model.json, or Name.rbxmx
+    // if they have properties Rojo can't read from JSON
+    ModelJson,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CollisionStrategy {
+    // Siblings after the first get " (2)", " (3)"... on disk, and keep their name through meta files
+    #[default]
+    Rename,
+    // Refuse to convert, listing every instance that collided
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ScriptExtension {
+    #[default]
+    Lua,
+    Luau,
+}
+
+impl ScriptExtension {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScriptExtension::Lua => "lua",
+            ScriptExtension::Luau => "luau",
+        }
+    }
+}
+
+// Decides which names are treated as the same file when checking for collisions
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Platform {
+    // Names must be distinct on every platform below
+    #[default]
+    Any,
+    // Names are compared byte for byte
+    Linux,
+    // Names are compared ignoring case
+    Windows,
+    // Names are compared ignoring case and Unicode normalization
+    MacOs,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ProjectKind {
+    // The root's children are services, added to a DataModel in default.project.json
+    #[default]
+    Place,
+    // The model's top instance is the source folder, and the root of default.project.json.
+    // A model with more than one top instance gets a Folder holding them all
+    Model,
+}
+
+fn service_list(list: &str) -> BTreeSet<String> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+// Unknown fields are rejected, so that a misspelt option isn't silently left at its default
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    pub collisions: CollisionStrategy,
+    pub platform: Platform,
+    pub script_extension: ScriptExtension,
+    // Write StringValues as .txt and LocalizationTables as .csv, even outside of scripts
+    pub native_file_types: bool,
+    pub non_script_instances: NonScriptInstances,
+    // Write non-default properties into meta files and the entries of StarterPlayer's children
+    pub export_properties: bool,
+    // Write services' non-default properties into their entries in the project
+    pub service_properties: bool,
+    // Services that are converted, any other service is left out.
+    // Defaults to the classes in respected-services.txt
+    pub respected_services: BTreeSet<String>,
+    // Respected services that get a folder but no entry in default.project.json.
+    // Defaults to the classes in non-tree-services.txt
+    pub non_tree_services: BTreeSet<String>,
+    // Patterns over full instance paths such as ReplicatedStorage.Shared.**, where * matches
+    // part of a name and ** any number of names. When there are any, only instances matching
+    // one (and their ancestors and descendants) are converted
+    pub include: Vec<String>,
+    // Instances matching any of these patterns are left out, along with their descendants
+    pub exclude: Vec<String>,
+    pub project_kind: ProjectKind,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            collisions: CollisionStrategy::default(),
+            platform: Platform::default(),
+            script_extension: ScriptExtension::default(),
+            native_file_types: false,
+            non_script_instances: NonScriptInstances::default(),
+            export_properties: false,
+            service_properties: true,
+            respected_services: service_list(include_str!("./respected-services.txt")),
+            non_tree_services: service_list(include_str!("./non-tree-services.txt")),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            project_kind: ProjectKind::default(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Instruction<'a> {
     AddToTree {
@@ -70,6 +234,10 @@ pub enum Instruction<'a> {
     CreateFolder {
         folder: Cow<'a, Path>,
     },
+
+    // The project is rooted at the source folder, which holds the model's top instance,
+    // instead of at a DataModel with services added to it
+    ModelRoot,
 }
 
 impl<'a> Instruction<'a> {
@@ -86,17 +254,26 @@ impl<'a> Instruction<'a> {
             children: BTreeMap::new(),
             ignore_unknown_instances: true,
             path: Some(path),
+            properties: BTreeMap::new(),
         }
     }
 }
 
 pub trait InstructionReader {
-    fn finish_instructions(&mut self) {}
-    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>);
+    fn finish_instructions(&mut self) -> Result<(), ConversionError> {
+        Ok(())
+    }
 
-    fn read_instructions<'a>(&mut self, instructions: Vec<Instruction<'a>>) {
+    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) -> Result<(), ConversionError>;
+
+    fn read_instructions<'a>(
+        &mut self,
+        instructions: Vec<Instruction<'a>>,
+    ) -> Result<(), ConversionError> {
         for instruction in instructions {
-            self.read_instruction(instruction);
+            self.read_instruction(instruction)?;
         }
+
+        Ok(())
     }
 }
\ No newline at end of file
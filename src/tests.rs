@@ -1,13 +1,24 @@
-use crate::{filesystem::FileSystem, process_instructions, structures::*};
+use crate::{
+    dry_run::DryRun,
+    error::ConversionError,
+    filesystem::{FileSystem, OutputMode},
+    full_name, model_top_instance, process_instructions_with_options,
+    rebuild::rebuild,
+    report::{DropReason, Report, RenameReason},
+    structures::*,
+    verify::verify,
+};
 use log::info;
 use pretty_assertions::assert_eq;
-use rbx_dom_weak::types::Variant;
+use rbx_dom_weak::{types::Variant, Ustr, WeakDom};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap},
-    fs,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File},
     io::ErrorKind,
-    time::Instant,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant, SystemTime},
 };
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
@@ -26,22 +37,39 @@ struct VirtualFile {
 struct VirtualFileSystem {
     files: BTreeMap<String, VirtualFile>,
     tree: BTreeMap<String, TreePartition>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    model_root: bool,
     #[serde(skip)]
     finished: bool,
 }
 
 impl PartialEq<VirtualFileSystem> for VirtualFileSystem {
     fn eq(&self, rhs: &VirtualFileSystem) -> bool {
-        self.files == rhs.files && self.tree == rhs.tree
+        self.files == rhs.files && self.tree == rhs.tree && self.model_root == rhs.model_root
+    }
+}
+
+impl VirtualFileSystem {
+    // Real file systems on macOS and Windows would merge these into one file
+    fn assert_distinct(&self, name: &str) {
+        let folded = name.to_lowercase();
+        if let Some(existing) = self
+            .files
+            .keys()
+            .find(|existing| *existing != name && existing.to_lowercase() == folded)
+        {
+            panic!("{:?} and {:?} differ only by case", existing, name);
+        }
     }
 }
 
 impl InstructionReader for VirtualFileSystem {
-    fn finish_instructions(&mut self) {
+    fn finish_instructions(&mut self) -> Result<(), ConversionError> {
         self.finished = true;
+        Ok(())
     }
 
-    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) {
+    fn read_instruction<'a>(&mut self, instruction: Instruction<'a>) -> Result<(), ConversionError> {
         match instruction {
             Instruction::AddToTree { name, partition } => {
                 self.tree.insert(name, partition);
@@ -73,6 +101,7 @@ impl InstructionReader for VirtualFileSystem {
                     }
                 };
 
+                system.assert_distinct(&filename);
                 let contents_string = String::from_utf8_lossy(&contents).into_owned();
                 let rbxmx = filename.ends_with(".rbxmx");
                 system.files.insert(
@@ -99,6 +128,7 @@ impl InstructionReader for VirtualFileSystem {
 
             Instruction::CreateFolder { folder } => {
                 let name = folder.to_string_lossy().replace("\\", "/");
+                self.assert_distinct(&name);
                 self.files.insert(
                     name,
                     VirtualFile {
@@ -106,7 +136,13 @@ impl InstructionReader for VirtualFileSystem {
                     },
                 );
             }
+
+            Instruction::ModelRoot => {
+                self.model_root = true;
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -130,9 +166,15 @@ fn run_tests() {
             Instant::now().duration_since(time).as_millis()
         );
 
+        let options: Options = match fs::read_to_string(path.join("options.json")) {
+            Ok(options) => serde_json::from_str(&options).expect("couldn't deserialize options.json"),
+            Err(_) => Options::default(),
+        };
+
         let mut vfs = VirtualFileSystem::default();
         let time = Instant::now();
-        process_instructions(&tree, &mut vfs);
+        process_instructions_with_options(&tree, &mut vfs, &options)
+            .expect("couldn't process instructions");
         info!(
             "processing instructions for {:?} took {}ms",
             path,
@@ -163,7 +205,379 @@ fn run_tests() {
 
         fs::create_dir(&filesystem_path).unwrap();
 
-        let mut filesystem = FileSystem::from_root(filesystem_path);
-        process_instructions(&tree, &mut filesystem);
+        let mut filesystem =
+            FileSystem::from_root(filesystem_path).expect("couldn't create filesystem");
+        process_instructions_with_options(&tree, &mut filesystem, &options)
+            .expect("couldn't write filesystem");
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn dry_run_lists_files() {
+    let source = fs::read_to_string("./test-files/folders-with-scripts/source.rbxmx")
+        .expect("couldn't read source.rbxmx");
+    let tree = rbx_xml::from_str_default(&source).expect("couldn't deserialize source.rbxmx");
+
+    let mut dry_run = DryRun::new(Vec::new(), "project");
+    process_instructions_with_options(&tree, &mut dry_run, &Options::default())
+        .expect("couldn't process instructions");
+
+    let output = String::from_utf8(dry_run.into_inner()).unwrap();
+    assert!(output.starts_with("src/ (4 files, "), "{}", output);
+    assert!(output.contains("\n  Folder/\n"), "{}", output);
+    assert!(output.contains("\n    Script.server.lua ("), "{}", output);
+    assert!(output.contains("\"name\": \"project\""), "{}", output);
+}
+
+// Every script in the given services, by full name, with its class and Source
+fn scripts_in(tree: &WeakDom, services: &[String]) -> BTreeMap<String, (String, String)> {
+    tree.descendants()
+        .filter(|instance| {
+            matches!(instance.class.as_str(), "Script" | "LocalScript" | "ModuleScript")
+        })
+        .map(|instance| (full_name(tree, instance), instance))
+        .filter(|(name, _)| services.iter().any(|service| name.split('.').next() == Some(service)))
+        .map(|(name, instance)| {
+            let source = match instance.properties.get(&Ustr::from("Source")) {
+                Some(Variant::String(source)) => source.clone(),
+                other => panic!("{} has no string Source: {:?}", name, other),
+            };
+
+            (name, (instance.class.to_string(), source))
+        })
+        .collect()
+}
+
+fn load_fixture(name: &str) -> WeakDom {
+    let source = fs::read_to_string(format!("./test-files/{}/source.rbxmx", name))
+        .expect("couldn't read source.rbxmx");
+    rbx_xml::from_str_default(&source).expect("couldn't deserialize source.rbxmx")
+}
+
+// A folder that no other test, or other run of the tests, will use
+fn temp_folder(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+
+    std::env::temp_dir().join(format!(
+        "rbxlx-to-rojo-{}-{}-{}-{}",
+        name,
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ))
+}
+
+// Converts a fixture into a new project, named the way the command line would name it
+fn convert_fixture(name: &str, options: &Options) -> (WeakDom, PathBuf, Report) {
+    let tree = load_fixture(name);
+    let top = model_top_instance(&tree, options).expect("couldn't filter the tree");
+    let project_name = match (options.project_kind, top) {
+        (ProjectKind::Model, Some(top)) => top.name.clone(),
+        _ => "project".to_owned(),
+    };
+
+    let project_path = temp_folder(name);
+    let mut filesystem = FileSystem::new(project_path.clone(), project_name, OutputMode::Fresh)
+        .expect("couldn't create filesystem");
+    let report = process_instructions_with_options(&tree, &mut filesystem, options)
+        .expect("couldn't write filesystem");
+
+    (tree, project_path, report)
+}
+
+#[test]
+fn rebuild_reads_back_scripts() {
+    let (tree, project_path, _) = convert_fixture("line-runner", &Options::default());
+
+    let rebuilt = rebuild(&project_path).expect("couldn't rebuild the project");
+    let services: Vec<String> = rebuilt
+        .root()
+        .children()
+        .iter()
+        .map(|referent| rebuilt.get_by_ref(*referent).unwrap().name.clone())
+        .collect();
+
+    let scripts = scripts_in(&rebuilt, &services);
+    assert!(!scripts.is_empty(), "no scripts were rebuilt");
+    assert_eq!(scripts_in(&tree, &services), scripts);
+
+    fs::remove_dir_all(&project_path).unwrap();
+}
+
+#[test]
+fn verify_finds_lost_scripts() {
+    let (tree, project_path, report) = convert_fixture("line-runner", &Options::default());
+
+    let verification = verify(&tree, &project_path, &report).expect("couldn't verify");
+    assert!(verification.is_ok(), "{}", verification);
+
+    let scripts = project_path.join("src/ServerStorage/Scripts");
+    fs::write(scripts.join("CoinMagnet.server.lua"), "-- changed").unwrap();
+    fs::remove_file(scripts.join("CoinScript.server.lua")).unwrap();
+    fs::write(scripts.join("Stray.lua"), "return nil").unwrap();
+
+    let verification = verify(&tree, &project_path, &report).expect("couldn't verify");
+    assert_eq!(verification.altered, vec!["ServerStorage.Scripts.CoinMagnet"]);
+    assert_eq!(verification.missing, vec!["ServerStorage.Scripts.CoinScript"]);
+    assert_eq!(verification.extra, vec!["ServerStorage.Scripts.Stray"]);
+
+    fs::remove_dir_all(&project_path).unwrap();
+}
+
+#[test]
+fn verify_model_project() {
+    let options = Options {
+        project_kind: ProjectKind::Model,
+        ..Options::default()
+    };
+    let (tree, project_path, report) = convert_fixture("model-project", &options);
+
+    // The root is named after the project, which is named after the top instance
+    let rebuilt = rebuild(&project_path).expect("couldn't rebuild the project");
+    let root = rebuilt.get_by_ref(rebuilt.root().children()[0]).unwrap();
+    assert_eq!(root.class.as_str(), "ModuleScript");
+    assert_eq!(root.name, "Library");
+
+    let verification = verify(&tree, &project_path, &report).expect("couldn't verify");
+    assert!(verification.is_ok(), "{}", verification);
+
+    fs::remove_dir_all(&project_path).unwrap();
+}
+
+fn write_project(tree: &WeakDom, root: &PathBuf, mode: OutputMode) -> FileSystem {
+    let mut filesystem =
+        FileSystem::new(root.clone(), "project", mode).expect("couldn't create filesystem");
+    process_instructions_with_options(tree, &mut filesystem, &Options::default())
+        .expect("couldn't write filesystem");
+    filesystem
+}
+
+#[test]
+fn fresh_refuses_non_empty_folder() {
+    let root = temp_folder("fresh");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("README.md"), "# My game").unwrap();
+
+    match FileSystem::new(root.clone(), "project", OutputMode::Fresh) {
+        Err(ConversionError::OutputNotEmpty(path)) => assert_eq!(path, root),
+        other => panic!("expected OutputNotEmpty, got {:?}", other.map(|_| ())),
+    }
+
+    assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "# My game");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+// A project converted once, with an old modification time on one of its files, a stale file
+// and folder in src, and files outside src that the converter never wrote
+fn existing_project(name: &str) -> (WeakDom, PathBuf, SystemTime) {
+    let tree = load_fixture("folders-with-scripts");
+    let root = temp_folder(name);
+    write_project(&tree, &root, OutputMode::Fresh);
+
+    let old = SystemTime::now() - Duration::from_secs(60 * 60);
+    File::options()
+        .write(true)
+        .open(root.join("src/Folder/Script.server.lua"))
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+
+    fs::write(root.join("src/Folder/Old.lua"), "return nil").unwrap();
+    fs::create_dir_all(root.join("src/Gone")).unwrap();
+    fs::write(root.join("src/Gone/Removed.lua"), "return nil").unwrap();
+    fs::write(root.join("README.md"), "# My game").unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join(".git/config"), "[core]").unwrap();
+
+    (tree, root, old)
+}
+
+#[test]
+fn merge_lists_stale_files() {
+    let (tree, root, old) = existing_project("merge");
+    let filesystem = write_project(&tree, &root, OutputMode::Merge { prune: false });
+
+    let modified = fs::metadata(root.join("src/Folder/Script.server.lua"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(modified, old, "an unchanged file was rewritten");
+
+    let stale: BTreeSet<PathBuf> = [
+        root.join("src/Folder/Old.lua"),
+        root.join("src/Gone/Removed.lua"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(filesystem.stale_files(), &stale);
+
+    assert!(root.join("src/Folder/Old.lua").exists());
+    assert!(root.join("README.md").exists());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn prune_only_deletes_stale_source_files() {
+    let (tree, root, _) = existing_project("prune");
+    write_project(&tree, &root, OutputMode::Merge { prune: true });
+
+    assert!(!root.join("src/Folder/Old.lua").exists());
+    assert!(!root.join("src/Gone").exists());
+    assert!(root.join("src/Folder/Script.server.lua").exists());
+    assert!(root.join("default.project.json").exists());
+    assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "# My game");
+    assert_eq!(fs::read_to_string(root.join(".git/config")).unwrap(), "[core]");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn model_files_leave_out_filtered_instances() {
+    let tree = load_fixture("path-filters-models");
+    let options: Options = serde_json::from_str(
+        &fs::read_to_string("./test-files/path-filters-models/options.json").unwrap(),
+    )
+    .unwrap();
+
+    let root = temp_folder("filtered-models");
+    let mut filesystem = FileSystem::from_root(root.clone()).expect("couldn't create filesystem");
+    process_instructions_with_options(&tree, &mut filesystem, &options)
+        .expect("couldn't write filesystem");
+
+    // Map keeps everything but the excluded Plugins_Old, and Lobby is only there for Spawn
+    for (file, expected) in [
+        ("Map.rbxmx", vec!["Ground", "Map"]),
+        ("Lobby.rbxmx", vec!["Lobby", "Spawn"]),
+    ] {
+        let source = fs::read_to_string(root.join("src/Workspace").join(file)).unwrap();
+        let model = rbx_xml::from_str_default(&source).expect("couldn't decode encoded xml");
+        let mut names: Vec<&str> = model
+            .descendants()
+            .filter(|instance| instance.referent() != model.root_ref())
+            .map(|instance| instance.name.as_str())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, expected, "{}", file);
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+// The fixture drops a folder of props and a StarterCharacterScripts with nothing in it that
+// runs, filters out a folder, skips Players and has a Script and ModuleScript both named Main
+#[test]
+fn report_counts_everything() {
+    let tree = load_fixture("report-counts");
+    let options: Options = serde_json::from_str(
+        &fs::read_to_string("./test-files/report-counts/options.json").unwrap(),
+    )
+    .unwrap();
+
+    let mut vfs = VirtualFileSystem::default();
+    let report = process_instructions_with_options(&tree, &mut vfs, &options)
+        .expect("couldn't process instructions");
+
+    assert_eq!(report.scripts, 4);
+    assert_eq!(
+        report.scripts_by_class,
+        BTreeMap::from([
+            ("LocalScript".to_owned(), 1),
+            ("ModuleScript".to_owned(), 1),
+            ("Script".to_owned(), 2),
+        ]),
+    );
+    assert_eq!(report.source_bytes, 54);
+
+    assert_eq!(
+        report.dropped_by_class,
+        BTreeMap::from([
+            ("Folder".to_owned(), 3),
+            ("StarterCharacterScripts".to_owned(), 1),
+        ]),
+    );
+    assert_eq!(
+        report.dropped_by_service,
+        BTreeMap::from([("StarterPlayer".to_owned(), 2), ("Workspace".to_owned(), 2)]),
+    );
+    assert_eq!(report.skipped_services, vec!["Players".to_owned()]);
+
+    let dropped: Vec<(&str, &str, DropReason)> = report
+        .dropped
+        .iter()
+        .map(|dropped| (dropped.instance.as_str(), dropped.class.as_str(), dropped.reason))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            ("Workspace.Props", "Folder", DropReason::NoScripts),
+            ("Workspace.Old", "Folder", DropReason::Filtered),
+            (
+                "StarterPlayer.StarterCharacterScripts",
+                "StarterCharacterScripts",
+                DropReason::NoScripts,
+            ),
+            ("Players", "Players", DropReason::ServiceNotRespected),
+        ],
+    );
+
+    let renamed: Vec<(&str, &str, RenameReason)> = report
+        .renamed
+        .iter()
+        .map(|rename| (rename.instance.as_str(), rename.file_name.as_str(), rename.reason))
+        .collect();
+    assert_eq!(
+        renamed,
+        vec![("ServerScriptService.Main", "Main (2)", RenameReason::Collision)],
+    );
+}
+
+#[test]
+fn terrain_isnt_written_as_a_model() {
+    let options = Options {
+        non_script_instances: NonScriptInstances::Rbxmx,
+        ..Options::default()
+    };
+
+    let tree = load_fixture("starter-player-models");
+    let mut vfs = VirtualFileSystem::default();
+    let report = process_instructions_with_options(&tree, &mut vfs, &options)
+        .expect("couldn't process instructions");
+
+    let dropped: Vec<(&str, DropReason)> = report
+        .dropped
+        .iter()
+        .map(|dropped| (dropped.instance.as_str(), dropped.reason))
+        .collect();
+    assert_eq!(dropped, vec![("Workspace.Terrain", DropReason::NotCreatable)]);
+}
+
+#[test]
+fn unexported_properties_are_reported() {
+    let options = Options {
+        export_properties: true,
+        ..Options::default()
+    };
+
+    let tree = load_fixture("unexported-properties");
+    let mut vfs = VirtualFileSystem::default();
+    let report = process_instructions_with_options(&tree, &mut vfs, &options)
+        .expect("couldn't process instructions");
+
+    let unexported: Vec<(&str, &str)> = report
+        .unexported_properties
+        .iter()
+        .map(|unexported| (unexported.instance.as_str(), unexported.property.as_str()))
+        .collect();
+    assert_eq!(unexported, vec![("Car", "PrimaryPart")]);
+}
+
+#[test]
+fn options_reject_unknown_fields() {
+    assert!(serde_json::from_str::<Options>(r#"{ "export_properties": true }"#).is_ok());
+    assert!(serde_json::from_str::<Options>(r#"{ "respected_service": ["Workspace"] }"#).is_err());
+}
@@ -0,0 +1,175 @@
+use crate::{
+    error::ConversionError,
+    full_name,
+    rebuild::read_project,
+    report::{DropReason, Report},
+    structures::ProjectKind,
+};
+use rbx_dom_weak::{types::Variant, Ustr, WeakDom};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, iter::repeat_n, path::Path};
+
+#[derive(Debug, PartialEq)]
+struct Script {
+    class: String,
+    source: Option<String>,
+}
+
+// The result of comparing a place's scripts with the ones its project builds into,
+// each listed by full path, such as ServerScriptService.Main
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Verification {
+    // Scripts in the place that the project doesn't have
+    pub missing: Vec<String>,
+    // Scripts in the project that the place doesn't have
+    pub extra: Vec<String>,
+    // Scripts in both whose class or Source differ
+    pub altered: Vec<String>,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.altered.is_empty()
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() {
+            return write!(formatter, "Every script made it into the project");
+        }
+
+        for (label, paths) in [
+            ("Missing", &self.missing),
+            ("Extra", &self.extra),
+            ("Altered", &self.altered),
+        ] {
+            for path in paths {
+                writeln!(formatter, "{}: {}", label, path)?;
+            }
+        }
+
+        write!(
+            formatter,
+            "{} missing, {} extra and {} altered scripts",
+            self.missing.len(),
+            self.extra.len(),
+            self.altered.len(),
+        )
+    }
+}
+
+// Siblings can share a name, so a path can have more than one script
+fn scripts(tree: &WeakDom) -> BTreeMap<String, Vec<Script>> {
+    let mut scripts: BTreeMap<String, Vec<Script>> = BTreeMap::new();
+
+    for instance in tree.descendants() {
+        if !matches!(instance.class.as_str(), "Script" | "LocalScript" | "ModuleScript") {
+            continue;
+        }
+
+        let source = match instance.properties.get(&Ustr::from("Source")) {
+            Some(Variant::String(source)) => Some(source.clone()),
+            _ => None,
+        };
+
+        scripts
+            .entry(full_name(tree, instance))
+            .or_default()
+            .push(Script {
+                class: instance.class.to_string(),
+                source,
+            });
+    }
+
+    scripts
+}
+
+fn compare(
+    original: BTreeMap<String, Vec<Script>>,
+    mut rebuilt: BTreeMap<String, Vec<Script>>,
+) -> Verification {
+    let mut verification = Verification::default();
+
+    for (path, mut expected) in original {
+        let mut found = rebuilt.remove(&path).unwrap_or_default();
+
+        // Scripts that match exactly are fine, whatever order the siblings were read in
+        expected.retain(|script| match found.iter().position(|other| other == script) {
+            Some(index) => {
+                found.remove(index);
+                false
+            }
+            None => true,
+        });
+
+        let altered = expected.len().min(found.len());
+        verification.altered.extend(repeat_n(path.clone(), altered));
+        verification.missing.extend(repeat_n(path.clone(), expected.len() - altered));
+        verification.extra.extend(repeat_n(path, found.len() - altered));
+    }
+
+    for (path, found) in rebuilt {
+        verification.extra.extend(repeat_n(path, found.len()));
+    }
+
+    verification
+}
+
+// Whether the instance at this path, or anything above it, was left out on purpose
+fn was_dropped(path: &str, report: &Report) -> bool {
+    report.dropped.iter().any(|dropped| {
+        path == dropped.instance
+            || path
+                .strip_prefix(dropped.instance.as_str())
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+// Builds the project in project_folder back into a place, the way Rojo would, and checks
+// that every script in the original place came back with the same class and Source.
+// Scripts the report says were left out on purpose aren't expected to come back.
+pub fn verify(
+    original: &WeakDom,
+    project_folder: &Path,
+    report: &Report,
+) -> Result<Verification, ConversionError> {
+    let (rebuilt, kind) = read_project(project_folder)?;
+
+    let mut expected = scripts(original);
+    expected.retain(|path, _| !was_dropped(path, report));
+
+    if kind == ProjectKind::Place {
+        return Ok(compare(expected, scripts(&rebuilt)));
+    }
+
+    let top_instances: Vec<&str> = original
+        .root()
+        .children()
+        .iter()
+        .filter_map(|referent| original.get_by_ref(*referent))
+        .map(|instance| instance.name.as_str())
+        .filter(|name| {
+            !report
+                .dropped
+                .iter()
+                .any(|dropped| dropped.reason == DropReason::Filtered && dropped.instance == *name)
+        })
+        .collect();
+
+    // A model project with one top instance is rooted at it, and named after it
+    if let [_] = top_instances.as_slice() {
+        return Ok(compare(expected, scripts(&rebuilt)));
+    }
+
+    // Otherwise its root is a Folder holding the top instances
+    let found = scripts(&rebuilt)
+        .into_iter()
+        .map(|(path, scripts)| match path.split_once('.') {
+            Some((_, path)) => (path.to_owned(), scripts),
+            None => (path, scripts),
+        })
+        .collect();
+
+    Ok(compare(expected, found))
+}
@@ -0,0 +1 @@
+return require(script.Util)